@@ -0,0 +1,8 @@
+#[macro_use]
+extern crate lazy_static;
+
+// modules
+pub mod mods;
+
+// public API
+pub use mods::{chain::Mockchain, error::MockchainError};
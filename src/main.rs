@@ -1,117 +1,97 @@
-#[macro_use]
-extern crate lazy_static;
-
-// modules
-mod mods;
-
 // std library
-use std::env::consts::OS;
-use std::path::{Path, PathBuf};
-use std::{thread, time};
+use std::env::{self, consts::OS};
+use std::fs;
+use std::process;
 
 // 3rd party crates
-use dirs::home_dir;
+use rpassword::prompt_password;
 
 // imports
-use mods::{
-    block::Block,
-    file::FileOps,
-    helpers::{create_transaction, create_wallet, mine_block, verify_chain},
-    log::{Log, LogLevel},
-    messaging::{display_msg, Message},
-    repl::Repl,
-    wallet::Wallet,
+use mockchain_v2::{
+    mods::{
+        cli::{Cli, Command, EXIT_FAILURE, EXIT_OK, EXIT_USAGE, USAGE},
+        hd::{account_path, generate_mnemonic},
+        keystore::{PasswordPrompt, PasswordRequest},
+        log::Log,
+        messaging::{display_msg, Message},
+        profile::{Profiles, DEFAULT_PROFILE},
+        render::{
+            render_blocks, render_data, render_object, render_supply, render_transactions,
+            render_verification, OutputFormat,
+        },
+        repl::Repl,
+        store::DataFile,
+    },
+    Mockchain, MockchainError,
 };
 
-// static references to data file paths
+// environment variable supplying key pair passwords to scripts
+const PASSWORD_VAR: &str = "MOCKCHAIN_PASSWORD";
 
-lazy_static! {
-    #[derive(Debug)]
-    static ref HOME: PathBuf = {
-        match home_dir() {
-            Some(dir) => dir,
-            None => panic!("Home directory not found to write data files, aborting"),
+fn main() {
+    // subcommands skip the interactive menu
+    let args: Vec<String> = env::args().skip(1).collect();
+    let Cli {
+        command,
+        output,
+        data_dir,
+        chain,
+    } = match Cli::parse(&args) {
+        Ok(cli) => cli,
+        Err(msg) => {
+            eprintln!("{}\n\n{}", msg, USAGE);
+            process::exit(EXIT_USAGE);
         }
     };
-}
-
-lazy_static! {
-    #[derive(Debug)]
-    static ref DATA_PATH: PathBuf = HOME.as_path().join(".mockchain").join("data");
-}
-
-lazy_static! {
-    #[derive(Debug)]
-    static ref LOG_PATH: PathBuf = HOME.as_path().join(".mockchain").join("log");
-}
-
-lazy_static! {
-    #[derive(Debug)]
-    static ref BLOCKCHAIN_PATH: PathBuf = DATA_PATH.as_path().join("blockchain.json");
-}
-
-lazy_static! {
-    #[derive(Debug)]
-    static ref KEYPAIRS_PATH: PathBuf = DATA_PATH.as_path().join("keypairs.json");
-}
-
-lazy_static! {
-    #[derive(Debug)]
-    static ref SIGNING_DATA_PATH: PathBuf = DATA_PATH.as_path().join("signing.json");
-}
-
-lazy_static! {
-    #[derive(Debug)]
-    static ref TRANSACTIONS_PATH: PathBuf = DATA_PATH.as_path().join("transactions.json");
-}
-
-lazy_static! {
-    #[derive(Debug)]
-    static ref WALLETS_PATH: PathBuf = DATA_PATH.as_path().join("wallets.json");
-}
-
-lazy_static! {
-    #[derive(Debug)]
-    static ref LOG_FILE_PATH: PathBuf = LOG_PATH.as_path().join("log.txt");
-}
-
-fn main() {
-    if !Path::new(BLOCKCHAIN_PATH.as_path()).exists() {
-        Log::init();
-
-        Log::new(LogLevel::INFO, 1, None);
-
-        FileOps::init(false);
-
-        Log::new(LogLevel::INFO, 2, None);
-
-        // sleep to allow init
-        let half_sec = time::Duration::from_millis(500);
-        thread::sleep(half_sec);
+    if command == Some(Command::Help) {
+        println!("{}", USAGE);
+        process::exit(EXIT_OK);
+    }
 
-        Block::add_genesis_block();
+    // --data-dir, then $MOCKCHAIN_HOME, then ~/.mockchain
+    let mut profile = chain.unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+    let opened = Profiles::locate(data_dir.as_deref())
+        .and_then(|profiles| Ok((profiles.open(&profile)?, profiles)));
+    let (mut chain, profiles) = match opened {
+        Ok(opened) => opened,
+        Err(e) => {
+            display_msg(Message::Failure(e.to_string(), None));
+            process::exit(EXIT_FAILURE);
+        }
+    };
+    chain.set_password_prompt(password_prompt());
 
-        Log::new(LogLevel::INFO, 3, None);
+    if let Some(command) = command {
+        let code = match command.run(&mut chain, output) {
+            Ok(true) => EXIT_OK,
+            Ok(false) => EXIT_FAILURE,
+            Err(e) => {
+                eprintln!("{}", e);
+                EXIT_FAILURE
+            }
+        };
+        process::exit(code);
     }
 
     Repl::print_intro();
+    warn_if_read_only(&chain);
     Repl::print_options();
 
     loop {
         print!("Select an option: ");
-        let _ = match Repl::get_input() {
+        match Repl::get_input() {
             Some(choice) => match choice {
                 0 => Repl::print_options(),
-                1 => option1(),
-                2 => option2(),
-                3 => option3(),
-                4 => println!("\n{:#?}\n", FileOps::parse(BLOCKCHAIN_PATH.as_path())),
-                5 => println!("\n{:#?}\n", FileOps::parse(TRANSACTIONS_PATH.as_path())),
-                6 => println!("\n{:#?}\n", FileOps::parse(WALLETS_PATH.as_path())),
-                7 => println!("\n{:#?}\n", FileOps::parse(KEYPAIRS_PATH.as_path())),
-                8 => println!("\n{:#?}\n", FileOps::parse(SIGNING_DATA_PATH.as_path())),
-                9 => option9(),
-                10 => println!("VALID CHAIN: {}", verify_chain()),
+                1 => option1(&mut chain),
+                2 => option2(&mut chain),
+                3 => option3(&mut chain),
+                4 => display_data(&chain, DataFile::Blockchain, output),
+                5 => display_data(&chain, DataFile::Transactions, output),
+                6 => display_data(&chain, DataFile::Wallets, output),
+                7 => display_data(&chain, DataFile::KeyPairs, output),
+                8 => display_data(&chain, DataFile::SigningData, output),
+                9 => option9(&mut chain),
+                10 => option10(&chain, output),
                 11 => {
                     if OS == "linux" || OS == "macos" {
                         println!("See you again soon! 👋 Your data files will be preserved 😃");
@@ -120,6 +100,17 @@ fn main() {
                     }
                     break;
                 }
+                12 => option12(&chain, output),
+                13 => option13(&profiles, &profile),
+                14 => option14(&profiles, &mut profile, &mut chain),
+                15 => option15(&profiles, &profile),
+                16 => option16(&profiles, &profile),
+                17 => option17(),
+                18 => option18(&mut chain),
+                19 => option19(&chain, output),
+                20 => option20(&chain, output),
+                21 => option21(&chain, output),
+                22 => option22(&chain),
                 _ => display_msg(Message::Failure(
                     "Please enter a valid integer to select an option".to_string(),
                     None,
@@ -135,43 +126,95 @@ fn main() {
 
 // Options helper functions
 
-fn option1() {
+fn report<T>(result: Result<T, MockchainError>) -> Option<T> {
+    match result {
+        Ok(val) => Some(val),
+        Err(e) => {
+            display_msg(Message::Failure(e.to_string(), None));
+            None
+        }
+    }
+}
+
+fn password_prompt() -> PasswordPrompt {
+    Box::new(|request| {
+        if let Ok(password) = env::var(PASSWORD_VAR) {
+            return Some(password);
+        }
+        match request {
+            PasswordRequest::New(name) => {
+                let password = prompt_password(format!("New password for '{}': ", name)).ok()?;
+                let repeated = prompt_password("Repeat the password: ").ok()?;
+                if password != repeated {
+                    display_msg(Message::Failure("Passwords do not match".to_string(), None));
+                    return None;
+                }
+                Some(password)
+            }
+            PasswordRequest::Unlock(name) => {
+                prompt_password(format!("Password for '{}': ", name)).ok()
+            }
+        }
+    })
+}
+
+fn warn_if_read_only(chain: &Mockchain) {
+    if let Some(pid) = chain.locked_by() {
+        display_msg(Message::Warning(
+            "Another mockchain process (PID {}) is using this chain; it has been opened read-only\n"
+                .to_string(),
+            Some(vec![pid]),
+        ));
+    }
+}
+
+fn display_data(chain: &Mockchain, file: DataFile, output: OutputFormat) {
+    if let Some(data) = report(render_data(chain, file, output)) {
+        println!("\n{}\n", data);
+    }
+}
+
+fn option1(chain: &mut Mockchain) {
     print!("Add a name for this wallet: ");
-    let _ = match Repl::get_input() {
+    match Repl::get_input::<String>() {
         Some(name) => {
-            if Wallet::name_exists(&name) {
-                display_msg(Message::Failure(
-                    "Wallet with name '{}' already exists".to_string(),
-                    Some(vec![name.clone()]),
-                ));
-                return;
+            match report(chain.wallet_exists(&name)) {
+                Some(false) => {}
+                Some(true) => {
+                    display_msg(Message::Failure(
+                        "Wallet with name '{}' already exists".to_string(),
+                        Some(vec![name.clone()]),
+                    ));
+                    return;
+                }
+                None => return,
             }
             display_msg(Message::Success(
                 "Creating wallet for '{}'".to_string(),
                 Some(vec![name.clone()]),
             ));
-            create_wallet(name);
-            display_msg(Message::Success("Wallet created".to_string(), None));
+            if report(chain.create_wallet(&name)).is_some() {
+                display_msg(Message::Success("Wallet created".to_string(), None));
+            }
         }
         None => display_msg(Message::Failure("Invalid name".to_string(), None)),
     };
 }
 
-fn option2() {
+fn option2(chain: &mut Mockchain) {
     print!("Name of account mining this block: ");
-    let _ = match Repl::get_input() {
+    match Repl::get_input::<String>() {
         Some(name) => {
-            if !Wallet::name_exists(&name) {
-                display_msg(Message::Failure(
-                    "No wallet found under name '{}'".to_string(),
-                    Some(vec![name.clone()]),
-                ));
-            } else {
-                mine_block(name.clone());
+            if let Some(block) = report(chain.mine_block(&name)) {
+                let amount = block.transactions.first().map_or(0, |t| t.amount);
                 display_msg(Message::Success(
-                    "New block mined successfully. A reward transaction has been added for '{}'"
+                    "New block mined successfully. '{}' was paid a coinbase of {} tokens that can be spent {} blocks later"
                         .to_string(),
-                    Some(vec![name.clone()]),
+                    Some(vec![
+                        name.clone(),
+                        amount.to_string(),
+                        chain.config().coinbase_maturity.to_string(),
+                    ]),
                 ));
             }
         }
@@ -179,99 +222,96 @@ fn option2() {
     };
 }
 
-fn option3() {
-    let mut senders_name = String::new();
-    let mut recipients_name = String::new();
-    let amount: i32;
+fn option3(chain: &mut Mockchain) {
     print!("Name on senders wallet: ");
-    let _ = match Repl::get_input() {
+    let senders_name = match Repl::get_input::<String>() {
         Some(name) => {
-            if !Wallet::name_exists(&name) {
-                display_msg(Message::Failure(
-                    "No wallet found under name '{}'".to_string(),
-                    Some(vec![name.clone()]),
-                ));
+            if report(chain.wallet_address(&name)).is_none() {
                 return;
             }
-            senders_name = name;
+            name
+        }
+        None => {
+            display_msg(Message::Failure("Invalid name".to_string(), None));
+            return;
         }
-        None => display_msg(Message::Failure("Invalid name".to_string(), None)),
     };
-    print!("Name on recipients wallet: ");
-    let _ = match Repl::get_input() {
-        Some(name) => {
-            if !Wallet::name_exists(&name) {
-                display_msg(Message::Failure(
-                    "No wallet found under name '{}'".to_string(),
-                    Some(vec![name.clone()]),
-                ));
+    print!("Name on recipients wallet or their address: ");
+    let recipient = match Repl::get_input::<String>() {
+        Some(recipient) => {
+            if report(chain.resolve_address(&recipient)).is_none() {
                 return;
             }
-            recipients_name = name;
+            recipient
+        }
+        None => {
+            display_msg(Message::Failure(
+                "Invalid name or address".to_string(),
+                None,
+            ));
+            return;
         }
-        None => display_msg(Message::Failure("Invalid name".to_string(), None)),
     };
     print!("Amount: ");
-    let _ = match Repl::get_input() {
-        Some(val) => {
-            amount = val;
-            if val <= 0 {
-                display_msg(Message::Failure(
-                    "Choose an amount greater than 0".to_string(),
-                    None,
-                ));
-                return;
-            }
-            if Wallet::get_balance(&senders_name) < amount {
-                display_msg(Message::Failure(
-                    "Not enough funds to send {} from {}'s account".to_string(),
-                    Some(vec![amount.to_string(), senders_name.clone()]),
-                ));
-                return;
-            }
+    let amount = match Repl::get_input::<i32>() {
+        Some(amount) => amount,
+        None => {
+            display_msg(Message::Failure(
+                "Please enter a positive whole number".to_string(),
+                None,
+            ));
+            return;
+        }
+    };
+    print!("Fee paid to the miner (0 for none): ");
+    match Repl::get_input::<i32>() {
+        Some(fee) => {
             display_msg(Message::Success(
                 "Adding new pending transaction\n".to_string(),
                 None,
             ));
-            if let Some(key) = Wallet::get_wallet_address(&senders_name) {
-                println!("\tSenders public key: {}", key);
+            if let Ok(address) = chain.wallet_address(&senders_name) {
+                println!("\tSenders address: {}", address);
             }
-            if let Some(key) = Wallet::get_wallet_address(&recipients_name) {
-                println!("\tRecipients public key: {}", key);
+            if let Ok(address) = chain.resolve_address(&recipient) {
+                println!("\tRecipients address: {}", address);
+            }
+            println!("\tAmount: {}", &amount);
+            println!("\tFee: {}\n", &fee);
+            if report(chain.submit_transaction_with_fee(&senders_name, &recipient, amount, fee))
+                .is_some()
+            {
+                display_msg(Message::Success(
+                    "Transaction added successfully".to_string(),
+                    None,
+                ));
             }
-            println!("\tAmount: {}\n", &amount);
-            create_transaction(senders_name, recipients_name, amount);
-            display_msg(Message::Success(
-                "Transaction added successfully".to_string(),
-                None,
-            ));
         }
         None => display_msg(Message::Failure(
-            "Please enter a positive whole number".to_string(),
+            "Please enter a whole number of 0 or more".to_string(),
             None,
         )),
     };
 }
 
-fn option9() {
-    fn helper(preserve: bool) {
+fn option9(chain: &mut Mockchain) {
+    fn helper(chain: &mut Mockchain, preserve: bool) {
         display_msg(Message::Success(
             "Re-initialising blockchain...".to_string(),
             None,
         ));
-        FileOps::init(preserve);
+        if report(chain.reinitialise(preserve)).is_none() {
+            return;
+        }
         display_msg(Message::Success(
             "Blockchain init completed successfully".to_string(),
             None,
         ));
-        let (msg, msg_key) = match preserve {
-            true => ("Wallet data has been preserved", 4),
-            false => ("Wallet data has been deleted", 5),
+        let msg = match preserve {
+            true => "Wallet data has been preserved",
+            false => "Wallet data has been deleted",
         };
         display_msg(Message::Success(msg.to_string(), None));
-        Log::new(LogLevel::WARNING, msg_key, None);
-        Block::add_genesis_block();
-        Log::new(LogLevel::INFO, 3, None);
     }
 
     display_msg(Message::Warning("!! This action will wipe out the current blockchain and transaction data. Continue? (y/n) ".to_string(), None));
@@ -291,17 +331,282 @@ fn option9() {
             };
             match keep.as_str() {
                 "y" => {
-                    helper(true);
+                    helper(chain, true);
                 }
                 "n" => {
-                    helper(false);
+                    helper(chain, false);
                 }
-                "Invalid option" => display_msg(Message::Failure(wipe, None)),
-                _ => display_msg(Message::Failure(wipe, None)),
+                _ => display_msg(Message::Failure(keep, None)),
             }
         }
         "n" => display_msg(Message::Success("Operation cancelled".to_string(), None)),
-        "Invalid option" => display_msg(Message::Failure(wipe, None)),
         _ => display_msg(Message::Failure(wipe, None)),
     };
 }
+
+fn option10(chain: &Mockchain, output: OutputFormat) {
+    let verification = match report(chain.verification_report()) {
+        Some(verification) => verification,
+        None => return,
+    };
+    if verification.valid {
+        display_msg(Message::Success(
+            "VALID CHAIN: all {} blocks verified".to_string(),
+            Some(vec![verification.blocks.to_string()]),
+        ));
+        return;
+    }
+    if let Some(rendered) = report(render_verification(&verification, output)) {
+        println!("\n{}\n", rendered);
+    }
+    display_msg(Message::Failure(
+        "INVALID CHAIN: verification found {} problems".to_string(),
+        Some(vec![verification.failures.len().to_string()]),
+    ));
+}
+
+fn option12(chain: &Mockchain, output: OutputFormat) {
+    print!("Hash of the mined transaction: ");
+    match Repl::get_input::<String>() {
+        Some(hash) => {
+            let proof = match report(chain.inclusion_proof(&hash)) {
+                Some(proof) => proof,
+                None => return,
+            };
+            if let Some(rendered) = report(render_object(&proof, output)) {
+                println!("\n{}\n", rendered);
+            }
+            match report(chain.verify_inclusion(&proof)) {
+                Some(true) => display_msg(Message::Success(
+                    "Proof verified against the merkle root of block {}".to_string(),
+                    Some(vec![proof.block_hash.clone()]),
+                )),
+                Some(false) => display_msg(Message::Failure(
+                    "Proof does not match the merkle root of block {}".to_string(),
+                    Some(vec![proof.block_hash.clone()]),
+                )),
+                None => {}
+            }
+        }
+        None => display_msg(Message::Failure("Invalid hash".to_string(), None)),
+    };
+}
+
+fn option13(profiles: &Profiles, current: &str) {
+    if let Some(names) = report(profiles.list()) {
+        println!();
+        for name in names {
+            let marker = if name == current { "*" } else { " " };
+            println!("{} {}", marker, name);
+        }
+        println!();
+    }
+}
+
+fn option14(profiles: &Profiles, current: &mut String, chain: &mut Mockchain) {
+    print!("Name of the chain profile to switch to: ");
+    match Repl::get_input::<String>() {
+        Some(name) => {
+            if name == *current {
+                display_msg(Message::Failure(
+                    "Chain profile '{}' is already in use".to_string(),
+                    Some(vec![name]),
+                ));
+            } else if let Some(opened) = report(profiles.open(&name)) {
+                *chain = opened;
+                chain.set_password_prompt(password_prompt());
+                *current = name;
+                display_msg(Message::Success(
+                    "Switched to chain profile '{}'".to_string(),
+                    Some(vec![current.clone()]),
+                ));
+                warn_if_read_only(chain);
+            } else {
+                // keep logging to the profile still in use
+                report(Log::init(&profiles.log_path(current)));
+            }
+        }
+        None => display_msg(Message::Failure("Invalid name".to_string(), None)),
+    };
+}
+
+fn option15(profiles: &Profiles, current: &str) {
+    print!("Name of the new chain profile: ");
+    match Repl::get_input::<String>() {
+        Some(name) => {
+            if report(profiles.clone_profile(current, &name)).is_some() {
+                display_msg(Message::Success(
+                    "Chain profile '{}' cloned into '{}'".to_string(),
+                    Some(vec![current.to_string(), name]),
+                ));
+            }
+        }
+        None => display_msg(Message::Failure("Invalid name".to_string(), None)),
+    };
+}
+
+fn option16(profiles: &Profiles, current: &str) {
+    print!("Name of the chain profile to delete: ");
+    let name = match Repl::get_input::<String>() {
+        Some(name) => name,
+        None => {
+            display_msg(Message::Failure("Invalid name".to_string(), None));
+            return;
+        }
+    };
+    if name == current {
+        display_msg(Message::Failure(
+            "Chain profile '{}' is in use; switch to another profile first".to_string(),
+            Some(vec![name]),
+        ));
+        return;
+    }
+    display_msg(Message::Warning(
+        "!! This action will delete every block, wallet and key pair of '{}'. Continue? (y/n) "
+            .to_string(),
+        Some(vec![name.clone()]),
+    ));
+    match Repl::get_input::<String>().as_deref() {
+        Some("y") => {
+            if report(profiles.delete(&name)).is_some() {
+                display_msg(Message::Success(
+                    "Chain profile '{}' deleted".to_string(),
+                    Some(vec![name]),
+                ));
+            }
+        }
+        _ => display_msg(Message::Success("Operation cancelled".to_string(), None)),
+    }
+}
+
+fn option17() {
+    if let Some(phrase) = report(generate_mnemonic()) {
+        display_msg(Message::Warning(
+            "Write these words down and keep them secret. Anyone holding them can rebuild your accounts:\n".to_string(),
+            None,
+        ));
+        println!("\n{}\n", phrase);
+    }
+}
+
+fn option18(chain: &mut Mockchain) {
+    print!("Add a name for this wallet: ");
+    let name = match Repl::get_input::<String>() {
+        Some(name) => name,
+        None => {
+            display_msg(Message::Failure("Invalid name".to_string(), None));
+            return;
+        }
+    };
+    print!("Seed phrase: ");
+    let phrase = match Repl::get_line() {
+        // the name is read as a single word, skip the rest of its line
+        Some(line) if line.is_empty() => Repl::get_line(),
+        line => line,
+    };
+    let phrase = match phrase {
+        Some(phrase) if !phrase.is_empty() => phrase,
+        _ => {
+            display_msg(Message::Failure("Invalid seed phrase".to_string(), None));
+            return;
+        }
+    };
+    print!("Passphrase (leave empty for none): ");
+    let passphrase = Repl::get_line().unwrap_or_default();
+    print!("Account index (0 for the first account): ");
+    let index = match Repl::get_input::<u32>() {
+        Some(index) => index,
+        None => {
+            display_msg(Message::Failure("Invalid account index".to_string(), None));
+            return;
+        }
+    };
+    if let Some(wallet) = report(chain.create_wallet_from_seed(&name, &phrase, &passphrase, index))
+    {
+        display_msg(Message::Success(
+            "Wallet '{}' restored from {} with address {}".to_string(),
+            Some(vec![wallet.name, account_path(index), wallet.address]),
+        ));
+    }
+}
+
+fn option19(chain: &Mockchain, output: OutputFormat) {
+    let pending = match report(chain.pending_by_fee()) {
+        Some(pending) => pending,
+        None => return,
+    };
+    if let Some(rendered) = report(render_transactions(&pending, output)) {
+        println!("\n{}\n", rendered);
+    }
+    display_msg(Message::Success(
+        "Blocks hold up to {} bytes of transactions besides the coinbase; higher fee rates are mined first".to_string(),
+        Some(vec![chain.config().max_block_size.to_string()]),
+    ));
+}
+
+fn option20(chain: &Mockchain, output: OutputFormat) {
+    let supply = match report(chain.supply_report()) {
+        Some(supply) => supply,
+        None => return,
+    };
+    if let Some(rendered) = report(render_supply(&supply, output)) {
+        println!("\n{}\n", rendered);
+    }
+    let policy = &chain.config().monetary_policy;
+    let halving = match policy.halving_interval {
+        0 => String::from("never halve"),
+        interval => format!("halve every {} blocks", interval),
+    };
+    display_msg(Message::Success(
+        "Rewards start at {} tokens, {} and stop at a supply of {}".to_string(),
+        Some(vec![
+            policy.initial_reward.to_string(),
+            halving,
+            policy.max_supply.to_string(),
+        ]),
+    ));
+}
+
+fn option21(chain: &Mockchain, output: OutputFormat) {
+    print!("Block height (0 for the genesis block): ");
+    let height = match Repl::get_input::<u64>() {
+        Some(height) => height,
+        None => {
+            display_msg(Message::Failure("Invalid block height".to_string(), None));
+            return;
+        }
+    };
+    let block = match report(chain.block_at(height)) {
+        Some(block) => block,
+        None => return,
+    };
+    if let Some(rendered) = report(render_blocks(&[block], output)) {
+        println!("\n{}\n", rendered);
+    }
+}
+
+fn option22(chain: &Mockchain) {
+    print!("File to export the report to: ");
+    let path = match Repl::get_line() {
+        Some(path) if !path.is_empty() => path,
+        _ => {
+            display_msg(Message::Failure("Invalid file path".to_string(), None));
+            return;
+        }
+    };
+    let verification = match report(chain.verification_report()) {
+        Some(verification) => verification,
+        None => return,
+    };
+    let json = match report(render_verification(&verification, OutputFormat::Pretty)) {
+        Some(json) => json,
+        None => return,
+    };
+    match fs::write(&path, json) {
+        Ok(_) => display_msg(Message::Success(
+            "Verification report of {} failures written to {}".to_string(),
+            Some(vec![verification.failures.len().to_string(), path]),
+        )),
+        Err(e) => display_msg(Message::Failure(e.to_string(), None)),
+    }
+}
@@ -0,0 +1,131 @@
+/*
+    Wallet addresses derived from public keys
+*/
+
+// 3rd party crates
+use hex::decode;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+// imports
+use super::error::MockchainError;
+
+// version byte of every address, 0x6f gives test network addresses starting with m or n
+pub const ADDRESS_VERSION: u8 = 0x6f;
+
+// bytes in a hash160 digest
+const HASH160_LEN: usize = 20;
+
+/// Derives the address of a public key. The key is
+/// hashed with SHA256 then RIPEMD160 and the digest is
+/// Base58Check encoded behind a version byte, so a
+/// mistyped address fails its checksum
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// public_key: &str -> hex encoded SEC1 public key
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<String, MockchainError>
+/// ```
+pub fn to_address(public_key: &str) -> Result<String, MockchainError> {
+    let key_bytes = match decode(public_key) {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return Err(MockchainError::Crypto(format!(
+                "Cannot decode public key: {}",
+                public_key
+            )))
+        }
+    };
+    let hash160 = Ripemd160::digest(Sha256::digest(key_bytes));
+    Ok(bs58::encode(hash160)
+        .with_check_version(ADDRESS_VERSION)
+        .into_string())
+}
+
+/// Checks an address decodes, carries the expected
+/// version byte and passes its checksum
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// address: &str -> Base58Check address
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<(), MockchainError>
+/// ```
+pub fn validate_address(address: &str) -> Result<(), MockchainError> {
+    match bs58::decode(address)
+        .with_check(Some(ADDRESS_VERSION))
+        .into_vec()
+    {
+        // the decoded bytes keep the version byte in front
+        Ok(bytes) if bytes.len() == HASH160_LEN + 1 => Ok(()),
+        _ => Err(MockchainError::InvalidAddress(address.to_string())),
+    }
+}
+
+/// Checks whether a string has the shape of an
+/// address, i.e. it is Base58 and decodes to a version
+/// byte, hash160 and checksum, without testing the
+/// checksum itself
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// value: &str -> string to check
+/// ```
+///
+/// # Returns
+/// ```
+/// bool
+/// ```
+pub fn looks_like_address(value: &str) -> bool {
+    match bs58::decode(value).into_vec() {
+        Ok(bytes) => bytes.len() == HASH160_LEN + 5,
+        Err(_) => false,
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_address {
+    use super::*;
+
+    #[test]
+    fn test_address_checksum() {
+        // uncompressed key of the private key 1, the generator point
+        let public_key = "046b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c2964fe342e2fe1a7f9b8ee7eb4a7c0f9e162bce33576b315ececbb6406837bf51f5";
+        let address = to_address(public_key).unwrap();
+        assert!(address.starts_with('m') || address.starts_with('n'));
+        assert_eq!(address, to_address(public_key).unwrap());
+        assert!(validate_address(&address).is_ok());
+
+        // any single changed character fails the checksum
+        let mut typo: Vec<char> = address.chars().collect();
+        typo[10] = if typo[10] == 'a' { 'b' } else { 'a' };
+        let typo: String = typo.into_iter().collect();
+        assert!(matches!(
+            validate_address(&typo),
+            Err(MockchainError::InvalidAddress(_))
+        ));
+        assert!(looks_like_address(&typo));
+        assert!(!looks_like_address("alice"));
+
+        // raw public keys are no longer addresses
+        assert!(validate_address(public_key).is_err());
+        assert!(to_address("not hex").is_err());
+    }
+}
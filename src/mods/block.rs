@@ -1,46 +1,53 @@
 // 3rd party crates
-use serde::Serialize;
-use serde_json::{to_string, to_value, Value};
+use serde::{Deserialize, Serialize};
 
 // imports
 use super::{
-    crypto::{get_merkle_root, hash_block},
-    file::FileOps,
-    helpers::get_timestamp,
-    log::{Log, LogLevel},
+    crypto::{hash_block, meets_difficulty},
+    error::MockchainError,
+    genesis::GenesisSpec,
+    store::{to_record, ChainStore, DataFile},
+    transaction::Transaction,
 };
-use crate::{BLOCKCHAIN_PATH, TRANSACTIONS_PATH};
 
-/// Defines a block to append to the chain
+// version of the header layout written to new blocks
+pub const BLOCK_VERSION: u32 = 1;
+
+/// Defines the header of a block. The block hash is
+/// computed over the header only, the transactions
+/// are bound to it through the merkle root
+///
+/// # Visibility
+/// public
 ///
 /// # Fields
 /// ```
-/// timestamp: String        -> timestamp of block creation
-/// hash: String             -> hash of this Block (default value of "0".repeat(64))
-/// previous_hash: String    -> "None" by default,
-/// nonce: u32               -> nonce used to produce this block
-/// transactions: [Value; 0] -> transactions in this Block
-/// merkle_root: String      -> hash of all transactions in this Block
+/// version: u32           -> version of the header layout
+/// height: u64            -> position of the Block in the chain, 0 for genesis
+/// previous_hash: String  -> hash of the previous Block, hash_chain_id(chain_id) for genesis
+/// merkle_root: String    -> merkle root of the transactions in the Block
+/// timestamp: String      -> timestamp of block creation
+/// difficulty: u32        -> leading zeros the hash of the Block must start with
+/// nonce: u64             -> nonce used to produce the hash of the Block
 /// ```
 ///
 /// # Derives
 /// ```
-/// serde::Serialize, Debug
+/// serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq
 /// ```
-#[derive(Serialize, Debug)]
-pub struct Block {
-    // num: u32,
-    pub timestamp: String,
-    pub hash: String,
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BlockHeader {
+    pub version: u32,
+    pub height: u64,
     pub previous_hash: String,
-    pub nonce: u32,
-    pub transactions: Value,
     pub merkle_root: String,
+    pub timestamp: String,
+    pub difficulty: u32,
+    pub nonce: u64,
 }
 
-impl Block {
-    /// Creates and writes the genesis block to
-    /// the blockchain
+impl BlockHeader {
+    /// Computes the hash of the header
     ///
     /// # Visibility
     /// public
@@ -49,43 +56,137 @@ impl Block {
     /// None
     ///
     /// # Returns
-    /// Nothing
-    pub fn add_genesis_block() {
-        let timestamp = get_timestamp();
-        let transactions = match to_string(&Value::Array([].to_vec())) {
-            Ok(val) => val,
-            Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 1, Some(vec!["Value::String".to_string()]));
-                panic!(
-                    "Unable to parse genesis block transaction to json_serde Value::String: {}",
-                    e
-                )
-            }
-        };
-        let transactions = match to_value(transactions) {
-            Ok(val) => val,
-            Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 1, Some(vec!["Value".to_string()]));
-                panic!(
-                    "Unable to parse genesis block transaction to json_serde Value: {}",
-                    e
-                )
-            }
-        };
-        let hash = hash_block(
-            &String::from("0"),
-            &String::from("N/A"),
-            &transactions.to_string(),
-        );
-        let merkle_root = get_merkle_root(TRANSACTIONS_PATH.as_path());
-        let genesis_block = Block {
-            timestamp,
-            hash,
-            previous_hash: String::from("N/A"),
-            nonce: 0,
-            transactions,
-            merkle_root,
+    /// ```
+    /// String
+    /// ```
+    pub fn hash(&self) -> String {
+        hash_block(self)
+    }
+
+    /// Counts the nonce up from its current value until
+    /// the hash of the header meets its difficulty. The
+    /// nonce is 64 bits wide so it cannot run out, even
+    /// at the 16^MAX_DIFFICULTY tries the highest
+    /// difficulty takes on average
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// String -> hash of the mined header
+    /// ```
+    pub fn mine(&mut self) -> String {
+        let mut hash = self.hash();
+        while !meets_difficulty(&hash, self.difficulty) {
+            self.nonce += 1;
+            hash = self.hash();
+        }
+        hash
+    }
+}
+
+/// Defines a block to append to the chain, split into
+/// a header and a body of transactions
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// header: BlockHeader             -> header the hash is computed over
+/// hash: String                    -> hash of the header of this Block
+/// transactions: Vec<Transaction>  -> transactions in this Block
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug, Clone
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Block {
+    pub header: BlockHeader,
+    pub hash: String,
+    pub transactions: Vec<Transaction>,
+}
+
+impl Block {
+    /// Creates and writes the genesis block described
+    /// by a genesis spec to the blockchain
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// store: &mut dyn ChainStore -> storage backend holding the chain
+    /// spec: &GenesisSpec         -> spec the genesis block is built from
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    pub fn add_genesis_block(
+        store: &mut dyn ChainStore,
+        spec: &GenesisSpec,
+    ) -> Result<(), MockchainError> {
+        store.write(
+            DataFile::Blockchain,
+            to_record(spec.block()?, DataFile::Blockchain)?,
+        )
+    }
+
+    /// Gets the Block at a height of the chain
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// store: &dyn ChainStore -> storage backend holding the chain
+    /// height: u64            -> height of the Block, 0 for genesis
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Block, MockchainError> -> BlockNotFound past the tip of the chain
+    /// ```
+    pub fn at_height(store: &dyn ChainStore, height: u64) -> Result<Block, MockchainError> {
+        match store
+            .blocks()?
+            .into_iter()
+            .find(|b| b.header.height == height)
+        {
+            Some(block) => Ok(block),
+            None => Err(MockchainError::BlockNotFound(height)),
+        }
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_block {
+    use super::*;
+
+    #[test]
+    fn test_mine_past_u32_nonces() {
+        // a search that would have wrapped a 32 bit nonce carries on
+        let mut header = BlockHeader {
+            version: BLOCK_VERSION,
+            height: 1,
+            previous_hash: "0".repeat(64),
+            merkle_root: "1".repeat(64),
+            timestamp: String::from("2024-01-01T00:00:00+00:00"),
+            difficulty: 1,
+            nonce: u32::MAX as u64,
         };
-        FileOps::write(BLOCKCHAIN_PATH.as_path(), "blockchain", genesis_block);
+        let hash = header.mine();
+        assert!(header.nonce > u32::MAX as u64);
+        assert!(meets_difficulty(&hash, 1));
+        assert_eq!(hash, header.hash());
     }
 }
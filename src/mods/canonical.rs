@@ -0,0 +1,102 @@
+/*
+    Canonical JSON encoding of the data that is hashed
+*/
+
+// 3rd party crates
+use serde_json::Value;
+
+/// Encodes a JSON value canonically: object keys are
+/// sorted, no whitespace is written and strings and
+/// numbers use the compact serde_json form. The same
+/// data always gives the same bytes however the JSON
+/// it was read from was formatted or ordered
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// value: &Value -> JSON value to encode
+/// ```
+///
+/// # Returns
+/// ```
+/// String
+/// ```
+pub fn canonical_json(value: &Value) -> String {
+    let mut encoded = String::new();
+    write_canonical(value, &mut encoded);
+    encoded
+}
+
+/// Appends the canonical encoding of a JSON value
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// value: &Value         -> JSON value to encode
+/// encoded: &mut String  -> encoding written so far
+/// ```
+///
+/// # Returns
+/// Nothing
+fn write_canonical(value: &Value, encoded: &mut String) {
+    match value {
+        Value::Array(items) => {
+            encoded.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    encoded.push(',');
+                }
+                write_canonical(item, encoded);
+            }
+            encoded.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            encoded.push('{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    encoded.push(',');
+                }
+                encoded.push_str(&Value::String(key.clone()).to_string());
+                encoded.push(':');
+                write_canonical(item, encoded);
+            }
+            encoded.push('}');
+        }
+        // scalars are already compact and unambiguous
+        scalar => encoded.push_str(&scalar.to_string()),
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_canonical {
+    use super::*;
+
+    use serde_json::{from_str, json};
+
+    #[test]
+    fn test_canonical_json() {
+        let value = json!({
+            "to": "bob",
+            "amount": 10,
+            "nested": {"b": [1, {"z": null, "a": true}], "a": "quote \" and -%-"},
+        });
+        assert_eq!(
+            r#"{"amount":10,"nested":{"a":"quote \" and -%-","b":[1,{"a":true,"z":null}]},"to":"bob"}"#,
+            canonical_json(&value)
+        );
+
+        // reordered and reformatted JSON encodes to the same bytes
+        let reformatted: Value = from_str(
+            "{\n  \"nested\" : { \"a\" : \"quote \\\" and -%-\",\n \"b\" : [ 1, { \"a\" : true, \"z\" : null } ] },\n  \"to\" : \"bob\",  \"amount\" : 10\n}",
+        )
+        .unwrap();
+        assert_eq!(canonical_json(&value), canonical_json(&reformatted));
+    }
+}
@@ -0,0 +1,768 @@
+/*
+    Public handle for embedding the simulator
+*/
+
+// std library
+use std::path::Path;
+
+// 3rd party crates
+use serde_json::Value;
+
+// imports
+use super::{
+    address::to_address,
+    block::Block,
+    config::ChainConfig,
+    crypto::{get_inclusion_proof, InclusionProof, KeyPair},
+    error::MockchainError,
+    file::FileOps,
+    hd::{account_path, mnemonic_to_seed, ExtendedKey},
+    helpers::{add_wallet, create_transaction, create_wallet, mine_block, verify_chain},
+    keystore::{Keystore, PasswordPrompt},
+    log::{Log, LogLevel},
+    memory::MemoryStore,
+    mempool::Mempool,
+    signing_data::Signing,
+    state::{BalanceMismatch, ChainState, SupplyPoint},
+    store::{atomically, ChainStore, DataFile},
+    transaction::Transaction,
+    verification::VerificationReport,
+    wallet::Wallet,
+};
+
+/// Handle on a single blockchain simulation. All
+/// operations run against the ChainStore the handle
+/// was opened with and report problems as errors
+/// rather than panicking
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// store: Box<dyn ChainStore> -> storage backend holding the chain
+/// config: ChainConfig         -> configuration used when mining
+/// keystore: Keystore          -> seals and unlocks private keys
+/// ```
+pub struct Mockchain {
+    store: Box<dyn ChainStore>,
+    config: ChainConfig,
+    keystore: Keystore,
+}
+
+impl Mockchain {
+    /// Opens a chain held in a storage backend. The
+    /// data sets and genesis block are created first
+    /// if the store has not been initialised yet
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// store: Box<dyn ChainStore> -> storage backend holding the chain
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Mockchain, MockchainError>
+    /// ```
+    pub fn new(store: Box<dyn ChainStore>) -> Result<Mockchain, MockchainError> {
+        Mockchain::with_config(store, ChainConfig::default())
+    }
+
+    /// Opens a chain held in a storage backend using a
+    /// custom configuration
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// store: Box<dyn ChainStore> -> storage backend holding the chain
+    /// config: ChainConfig         -> configuration used when mining
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Mockchain, MockchainError>
+    /// ```
+    pub fn with_config(
+        store: Box<dyn ChainStore>,
+        config: ChainConfig,
+    ) -> Result<Mockchain, MockchainError> {
+        config.validate()?;
+        let keystore = Keystore::new(config.plaintext_keys);
+        let mut chain = Mockchain {
+            store,
+            config,
+            keystore,
+        };
+        chain.store.recover()?;
+        if !chain.store.exists() {
+            atomically(chain.store.as_mut(), |store| {
+                store.init(false)?;
+                Log::new(LogLevel::INFO, 2, None);
+                Block::add_genesis_block(store, &chain.config.genesis)?;
+                Log::new(LogLevel::INFO, 3, None);
+                Ok(())
+            })?;
+        }
+        Ok(chain)
+    }
+
+    /// Opens a chain kept as JSON files in a data directory,
+    /// holding the lock on the directory until the chain
+    /// is dropped
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// data_path: &Path -> directory holding the JSON data files
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Mockchain, MockchainError> -> Locked if another process holds the lock
+    /// ```
+    pub fn open(data_path: &Path) -> Result<Mockchain, MockchainError> {
+        Mockchain::new(Box::new(FileOps::locked(data_path)?))
+    }
+
+    /// Creates a chain that only lives in memory. Nothing
+    /// is kept at rest so private keys are not encrypted
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Mockchain, MockchainError>
+    /// ```
+    pub fn in_memory() -> Result<Mockchain, MockchainError> {
+        let config = ChainConfig {
+            plaintext_keys: true,
+            ..ChainConfig::default()
+        };
+        Mockchain::with_config(Box::new(MemoryStore::new()), config)
+    }
+
+    /// Gets the storage backend holding the chain
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// &dyn ChainStore
+    /// ```
+    pub fn store(&self) -> &dyn ChainStore {
+        self.store.as_ref()
+    }
+
+    /// Gets the configuration used when mining
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// &ChainConfig
+    /// ```
+    pub fn config(&self) -> &ChainConfig {
+        &self.config
+    }
+
+    /// Sets the callback asked for passwords when a key
+    /// pair is created or a private key is needed to sign
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// prompt: PasswordPrompt -> callback asked for passwords
+    /// ```
+    ///
+    /// # Returns
+    /// Nothing
+    pub fn set_password_prompt(&mut self, prompt: PasswordPrompt) {
+        self.keystore.set_prompt(prompt);
+    }
+
+    /// Gets the PID of the process whose lock forced
+    /// this chain to be opened read-only
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Option<String> -> None if the chain can be written to
+    /// ```
+    pub fn locked_by(&self) -> Option<String> {
+        self.store.locked_by()
+    }
+
+    /// Creates a wallet and key pair for a new account
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str -> unique name of the account
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Wallet, MockchainError>
+    /// ```
+    pub fn create_wallet(&mut self, name: &str) -> Result<Wallet, MockchainError> {
+        if self.wallet_exists(name)? {
+            return Err(MockchainError::WalletExists(name.to_string()));
+        }
+        let keystore = &mut self.keystore;
+        atomically(self.store.as_mut(), |store| {
+            create_wallet(store, name, keystore)
+        })
+    }
+
+    /// Creates a wallet for an account derived from a
+    /// seed phrase. The same phrase, passphrase and index
+    /// always give the same key, so wallets can be rebuilt
+    /// after the chain is reinitialised
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str       -> unique name of the account
+    /// phrase: &str     -> BIP39 seed phrase
+    /// passphrase: &str -> optional BIP39 passphrase, "" for none
+    /// index: u32       -> account index below hd::ACCOUNT_PATH
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Wallet, MockchainError>
+    /// ```
+    pub fn create_wallet_from_seed(
+        &mut self,
+        name: &str,
+        phrase: &str,
+        passphrase: &str,
+        index: u32,
+    ) -> Result<Wallet, MockchainError> {
+        if self.wallet_exists(name)? {
+            return Err(MockchainError::WalletExists(name.to_string()));
+        }
+        let seed = mnemonic_to_seed(phrase, passphrase)?;
+        let path = account_path(index);
+        let key = ExtendedKey::derive(&seed, &path)?;
+        let mut key_pair = KeyPair::from_secret(name.to_string(), &key.secret);
+        key_pair.path = Some(path);
+        let address = to_address(&key_pair.public_key)?;
+        if let Some(owner) = Wallet::get_wallet_name(self.store.as_ref(), &address)? {
+            return Err(MockchainError::KeyExists(owner));
+        }
+        let keystore = &mut self.keystore;
+        atomically(self.store.as_mut(), |store| {
+            add_wallet(store, key_pair, keystore)
+        })
+    }
+
+    /// Adds a signed transaction to the pending transactions
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// from: &str  -> name on the senders wallet
+    /// to: &str    -> name on the recipients wallet or any address
+    /// amount: i32 -> amount to send
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Transaction, MockchainError>
+    /// ```
+    pub fn submit_transaction(
+        &mut self,
+        from: &str,
+        to: &str,
+        amount: i32,
+    ) -> Result<Transaction, MockchainError> {
+        self.submit_transaction_with_fee(from, to, amount, 0)
+    }
+
+    /// Adds a signed transaction paying a fee to the
+    /// miner to the pending transactions. Higher fee
+    /// rates are mined first when blocks are full
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// from: &str  -> name on the senders wallet
+    /// to: &str    -> name on the recipients wallet or any address
+    /// amount: i32 -> amount to send
+    /// fee: i32    -> fee paid to the miner on top of the amount
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Transaction, MockchainError>
+    /// ```
+    pub fn submit_transaction_with_fee(
+        &mut self,
+        from: &str,
+        to: &str,
+        amount: i32,
+        fee: i32,
+    ) -> Result<Transaction, MockchainError> {
+        if !self.wallet_exists(from)? {
+            return Err(MockchainError::WalletNotFound(from.to_string()));
+        }
+        self.resolve_address(to)?;
+        if amount <= 0 {
+            return Err(MockchainError::InvalidAmount(amount));
+        }
+        if fee < 0 {
+            return Err(MockchainError::InvalidFee(fee));
+        }
+        let cost = Transaction::total(amount, fee)?;
+        if self.spendable_balance(from)? < cost {
+            return Err(MockchainError::InsufficientFunds(from.to_string(), cost));
+        }
+        let keystore = &mut self.keystore;
+        atomically(self.store.as_mut(), |store| {
+            create_transaction(store, from, to, amount, fee, keystore)
+        })
+    }
+
+    /// Mines the pending transactions into a new block
+    /// led by a coinbase paying the miner
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// miner: &str -> name on the miners wallet
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Block, MockchainError>
+    /// ```
+    pub fn mine_block(&mut self, miner: &str) -> Result<Block, MockchainError> {
+        if !self.wallet_exists(miner)? {
+            return Err(MockchainError::WalletNotFound(miner.to_string()));
+        }
+        let config = &self.config;
+        let keystore = &mut self.keystore;
+        atomically(self.store.as_mut(), |store| {
+            mine_block(store, miner, config, keystore)
+        })
+    }
+
+    /// Verifies the integrity of the blockchain
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<bool, MockchainError>
+    /// ```
+    pub fn verify(&self) -> Result<bool, MockchainError> {
+        Ok(self.verification_report()?.valid)
+    }
+
+    /// Verifies the integrity of the blockchain and
+    /// reports every failure found
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<VerificationReport, MockchainError>
+    /// ```
+    pub fn verification_report(&self) -> Result<VerificationReport, MockchainError> {
+        verify_chain(self.store.as_ref(), &self.config)
+    }
+
+    /// Wipes the chain and pending transactions and
+    /// mines a fresh genesis block
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// preserve_accounts: bool -> option to preserve wallet and key data
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    pub fn reinitialise(&mut self, preserve_accounts: bool) -> Result<(), MockchainError> {
+        atomically(self.store.as_mut(), |store| {
+            store.init(preserve_accounts)?;
+            let msg_key = match preserve_accounts {
+                true => 4,
+                false => 5,
+            };
+            Log::new(LogLevel::WARNING, msg_key, None);
+            Block::add_genesis_block(store, &self.config.genesis)?;
+            Log::new(LogLevel::INFO, 3, None);
+            Ok(())
+        })
+    }
+
+    /// Checks whether a wallet exists under a name
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str -> name to check for
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<bool, MockchainError>
+    /// ```
+    pub fn wallet_exists(&self, name: &str) -> Result<bool, MockchainError> {
+        Wallet::name_exists(self.store.as_ref(), name)
+    }
+
+    /// Gets the address of the wallet under a name
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str -> name on the wallet
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<String, MockchainError>
+    /// ```
+    pub fn wallet_address(&self, name: &str) -> Result<String, MockchainError> {
+        match Wallet::get_wallet_address(self.store.as_ref(), name)? {
+            Some(address) => Ok(address),
+            None => Err(MockchainError::WalletNotFound(name.to_string())),
+        }
+    }
+
+    /// Gets the address a transfer to a wallet name or
+    /// an address would be paid to
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// recipient: &str -> name on a local wallet or any address
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<String, MockchainError>
+    /// ```
+    pub fn resolve_address(&self, recipient: &str) -> Result<String, MockchainError> {
+        Wallet::resolve_address(self.store.as_ref(), recipient)
+    }
+
+    /// Gets the confirmed balance of the wallet under a name
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str -> name on the wallet
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<i32, MockchainError>
+    /// ```
+    pub fn balance(&self, name: &str) -> Result<i32, MockchainError> {
+        Wallet::get_balance(self.store.as_ref(), name)
+    }
+
+    /// Gets the balance the wallet under a name can still
+    /// spend once its pending outgoing transfers are paid,
+    /// leaving out coinbases that have not matured
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str -> name on the wallet
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<i32, MockchainError>
+    /// ```
+    pub fn spendable_balance(&self, name: &str) -> Result<i32, MockchainError> {
+        let address = self.wallet_address(name)?;
+        Mempool::spendable_balance(self.store.as_ref(), &address, self.config.coinbase_maturity)
+    }
+
+    /// Compares the balances cached in wallets.json with
+    /// the balances given by replaying the chain
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<BalanceMismatch>, MockchainError>
+    /// ```
+    pub fn balance_mismatches(&self) -> Result<Vec<BalanceMismatch>, MockchainError> {
+        let state = ChainState::from_store(self.store.as_ref())?;
+        Ok(state.mismatches(&self.store.wallets()?))
+    }
+
+    /// Gets the circulating supply at every height of
+    /// the chain under its monetary policy
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<SupplyPoint>, MockchainError>
+    /// ```
+    pub fn supply_report(&self) -> Result<Vec<SupplyPoint>, MockchainError> {
+        Ok(ChainState::supply(
+            &self.store.blocks()?,
+            &self.config.monetary_policy,
+        ))
+    }
+
+    /// Reads one of the chains data sets as JSON
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// file: DataFile -> data set to read
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Value, MockchainError>
+    /// ```
+    pub fn data(&self, file: DataFile) -> Result<Value, MockchainError> {
+        self.store.parse(file)
+    }
+
+    /// Gets every Block in the chain
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<Block>, MockchainError>
+    /// ```
+    pub fn blocks(&self) -> Result<Vec<Block>, MockchainError> {
+        self.store.blocks()
+    }
+
+    /// Gets the Block at a height of the chain
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// height: u64 -> height of the Block, 0 for genesis
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Block, MockchainError> -> BlockNotFound past the tip of the chain
+    /// ```
+    pub fn block_at(&self, height: u64) -> Result<Block, MockchainError> {
+        Block::at_height(self.store.as_ref(), height)
+    }
+
+    /// Gets every pending Transaction
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<Transaction>, MockchainError>
+    /// ```
+    pub fn pending_transactions(&self) -> Result<Vec<Transaction>, MockchainError> {
+        self.store.transactions()
+    }
+
+    /// Gets every pending Transaction ordered by fee rate,
+    /// highest first
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<Transaction>, MockchainError>
+    /// ```
+    pub fn pending_by_fee(&self) -> Result<Vec<Transaction>, MockchainError> {
+        Mempool::by_fee_rate(self.store.as_ref())
+    }
+
+    /// Gets every Wallet
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<Wallet>, MockchainError>
+    /// ```
+    pub fn wallets(&self) -> Result<Vec<Wallet>, MockchainError> {
+        self.store.wallets()
+    }
+
+    /// Gets every KeyPair
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<KeyPair>, MockchainError>
+    /// ```
+    pub fn keypairs(&self) -> Result<Vec<KeyPair>, MockchainError> {
+        self.store.keypairs()
+    }
+
+    /// Gets every Signing record
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<Signing>, MockchainError>
+    /// ```
+    pub fn signing_data(&self) -> Result<Vec<Signing>, MockchainError> {
+        self.store.signing_data()
+    }
+
+    /// Creates an inclusion proof for a mined transaction
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// transaction_hash: &str -> hash of the transaction to prove
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<InclusionProof, MockchainError>
+    /// ```
+    pub fn inclusion_proof(
+        &self,
+        transaction_hash: &str,
+    ) -> Result<InclusionProof, MockchainError> {
+        for block in self.store.blocks()?.iter() {
+            if let Some(proof) = get_inclusion_proof(block, transaction_hash) {
+                return Ok(proof);
+            }
+        }
+        Err(MockchainError::TransactionNotFound(
+            transaction_hash.to_string(),
+        ))
+    }
+
+    /// Verifies an inclusion proof against the merkle root
+    /// of the block it was created for. Proofs for blocks
+    /// that are not in the chain fail verification
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// proof: &InclusionProof -> proof to verify
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<bool, MockchainError>
+    /// ```
+    pub fn verify_inclusion(&self, proof: &InclusionProof) -> Result<bool, MockchainError> {
+        let blocks = self.store.blocks()?;
+        match blocks.iter().find(|b| b.hash == proof.block_hash) {
+            Some(block) => Ok(proof.verify(&block.header.merkle_root)),
+            None => Ok(false),
+        }
+    }
+}
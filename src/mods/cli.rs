@@ -0,0 +1,395 @@
+/*
+    Non-interactive subcommands for scripting the simulator
+*/
+
+// 3rd party crates
+use serde_json::{json, to_value};
+
+// imports
+use super::{
+    chain::Mockchain,
+    error::MockchainError,
+    render::{
+        render_blocks, render_data, render_object, render_supply, render_transactions,
+        render_verification, render_wallets, OutputFormat,
+    },
+    store::DataFile,
+};
+
+// process exit codes
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+
+pub const USAGE: &str = "Usage: mockchain [OPTIONS] [COMMAND]
+
+Starts the interactive menu when no command is given.
+
+Options:
+    --output json|table|pretty     Format of the menu and command output (default: pretty)
+    --data-dir <path>              Directory holding every chain (default: $MOCKCHAIN_HOME
+                                   or ~/.mockchain)
+    --chain <name>                 Named chain profile to use (default: default)
+
+Commands:
+    wallet create <name>           Create a wallet and key pair
+    wallet balance <name>          Show the balance of a wallet
+    tx send <from> <to> <amount> [fee]
+                                   Send to a wallet name or address, paying an optional fee
+    mine <miner>                   Mine the pending transactions into a block
+    verify                         Verify the blockchain and list every failure found,
+                                   exits with 1 if invalid
+    prove <transaction hash>       Prove a mined transaction is in a block
+    block <height>                 Show the block at a height, 0 for genesis
+    show <data>                    Show blocks, pending, wallets, keypairs or signatures
+    supply                         Show the circulating supply by block height
+    init [--preserve-accounts]     Re-initialise the blockchain
+    help                           Show this message";
+
+/// Parsed command line made of an optional Command,
+/// the format output is rendered in and the chain
+/// profile to open
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// command: Option<Command>  -> command to run, None starts the menu
+/// output: OutputFormat      -> format output is rendered in
+/// data_dir: Option<String>  -> directory holding every chain profile
+/// chain: Option<String>     -> name of the chain profile to open
+/// ```
+///
+/// # Derives
+/// ```
+/// Debug, PartialEq
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct Cli {
+    pub command: Option<Command>,
+    pub output: OutputFormat,
+    pub data_dir: Option<String>,
+    pub chain: Option<String>,
+}
+
+impl Cli {
+    /// Parses the command line arguments, without the
+    /// program name. The --output, --data-dir and --chain
+    /// options may appear anywhere in the arguments
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// args: &[String] -> command line arguments
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Cli, String> -> usage error message on failure
+    /// ```
+    pub fn parse(args: &[String]) -> Result<Cli, String> {
+        let mut output = OutputFormat::default();
+        let mut data_dir = None;
+        let mut chain = None;
+        let mut rest: Vec<String> = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let (option, inline) = match arg.split_once('=') {
+                Some((option, value)) => (option, Some(value.to_string())),
+                None => (arg.as_str(), None),
+            };
+            if !["--output", "--data-dir", "--chain"].contains(&option) {
+                rest.push(arg.clone());
+                continue;
+            }
+            let value = match inline.or_else(|| args.next().cloned()) {
+                Some(value) => value,
+                None => return Err(format!("Missing value for {}", option)),
+            };
+            match option {
+                "--output" => output = value.parse()?,
+                "--data-dir" => data_dir = Some(value),
+                _ => chain = Some(value),
+            }
+        }
+        Ok(Cli {
+            command: Command::parse(&rest)?,
+            output,
+            data_dir,
+            chain,
+        })
+    }
+}
+
+/// Subcommands that can be run without the
+/// interactive menu
+///
+/// # Visibility
+/// public
+///
+/// # Variants
+/// ```
+/// WalletCreate(String)            -> name of the new wallet
+/// WalletBalance(String)           -> name on the wallet
+/// Send(String, String, i32, i32)  -> sender, recipient, amount and fee
+/// Mine(String)                    -> name on the miners wallet
+/// Verify
+/// Prove(String)                   -> hash of a mined transaction
+/// Block(u64)                      -> height of the block to print
+/// Show(DataFile)                  -> data set to print
+/// Supply
+/// Init(bool)                      -> option to preserve wallet and key data
+/// Help
+/// ```
+///
+/// # Derives
+/// ```
+/// Debug, PartialEq
+/// ```
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    WalletCreate(String),
+    WalletBalance(String),
+    Send(String, String, i32, i32),
+    Mine(String),
+    Verify,
+    Prove(String),
+    Block(u64),
+    Show(DataFile),
+    Supply,
+    Init(bool),
+    Help,
+}
+
+impl Command {
+    /// Parses the command line arguments, without the
+    /// program name, into a Command. No arguments means
+    /// the interactive menu should be started instead
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// args: &[String] -> command line arguments
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Option<Command>, String> -> usage error message on failure
+    /// ```
+    pub fn parse(args: &[String]) -> Result<Option<Command>, String> {
+        let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+        let command = match args.as_slice() {
+            [] => return Ok(None),
+            ["help"] | ["--help"] | ["-h"] => Command::Help,
+            ["wallet", "create", name] => Command::WalletCreate(name.to_string()),
+            ["wallet", "balance", name] => Command::WalletBalance(name.to_string()),
+            ["tx", "send", from, to, amount] => match amount.parse::<i32>() {
+                Ok(amount) => Command::Send(from.to_string(), to.to_string(), amount, 0),
+                Err(_) => return Err(format!("Invalid amount '{}'", amount)),
+            },
+            ["tx", "send", from, to, amount, fee] => {
+                match (amount.parse::<i32>(), fee.parse::<i32>()) {
+                    (Ok(amount), Ok(fee)) => {
+                        Command::Send(from.to_string(), to.to_string(), amount, fee)
+                    }
+                    (Err(_), _) => return Err(format!("Invalid amount '{}'", amount)),
+                    (_, Err(_)) => return Err(format!("Invalid fee '{}'", fee)),
+                }
+            }
+            ["mine", miner] => Command::Mine(miner.to_string()),
+            ["verify"] => Command::Verify,
+            ["supply"] => Command::Supply,
+            ["prove", hash] => Command::Prove(hash.to_string()),
+            ["block", height] => match height.parse::<u64>() {
+                Ok(height) => Command::Block(height),
+                Err(_) => return Err(format!("Invalid block height '{}'", height)),
+            },
+            ["show", data] => match *data {
+                "blocks" | "blockchain" => Command::Show(DataFile::Blockchain),
+                "pending" | "transactions" => Command::Show(DataFile::Transactions),
+                "wallets" => Command::Show(DataFile::Wallets),
+                "keypairs" => Command::Show(DataFile::KeyPairs),
+                "signatures" | "signing" => Command::Show(DataFile::SigningData),
+                _ => return Err(format!("Unknown data set '{}'", data)),
+            },
+            ["init"] => Command::Init(false),
+            ["init", "--preserve-accounts"] => Command::Init(true),
+            _ => return Err(format!("Unknown command '{}'", args.join(" "))),
+        };
+        Ok(Some(command))
+    }
+
+    /// Runs a Command against a chain and prints its
+    /// result to stdout
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// chain: &mut Mockchain -> chain to run the command against
+    /// output: OutputFormat  -> format the result is rendered in
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<bool, MockchainError> -> false if the command ran but did not succeed
+    /// ```
+    pub fn run(&self, chain: &mut Mockchain, output: OutputFormat) -> Result<bool, MockchainError> {
+        let mut success = true;
+        let rendered = match self {
+            Command::Help => USAGE.to_string(),
+            Command::WalletCreate(name) => render_wallets(&[chain.create_wallet(name)?], output)?,
+            Command::WalletBalance(name) => {
+                // surface unknown names rather than printing 0
+                let address = chain.wallet_address(name)?;
+                let balance = json!({
+                    "name": name,
+                    "address": address,
+                    "balance": chain.balance(name)?,
+                    "spendable": chain.spendable_balance(name)?,
+                });
+                render_object(&balance, output)?
+            }
+            Command::Send(from, to, amount, fee) => {
+                let transaction = chain.submit_transaction_with_fee(from, to, *amount, *fee)?;
+                render_transactions(&[transaction], output)?
+            }
+            Command::Mine(miner) => render_blocks(&[chain.mine_block(miner)?], output)?,
+            Command::Block(height) => render_blocks(&[chain.block_at(*height)?], output)?,
+            Command::Verify => {
+                let report = chain.verification_report()?;
+                success = report.valid;
+                render_verification(&report, output)?
+            }
+            Command::Prove(hash) => {
+                let proof = chain.inclusion_proof(hash)?;
+                success = chain.verify_inclusion(&proof)?;
+                let mut report = match to_value(&proof) {
+                    Ok(report) => report,
+                    Err(e) => return Err(MockchainError::Json(e.to_string())),
+                };
+                report["verified"] = json!(success);
+                render_object(&report, output)?
+            }
+            Command::Show(file) => render_data(chain, *file, output)?,
+            Command::Supply => render_supply(&chain.supply_report()?, output)?,
+            Command::Init(preserve_accounts) => {
+                chain.reinitialise(*preserve_accounts)?;
+                return Ok(true);
+            }
+        };
+        println!("{}", rendered);
+        Ok(success)
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_cli {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(None, Command::parse(&[]).unwrap());
+        assert_eq!(
+            Some(Command::Send(
+                String::from("alice"),
+                String::from("bob"),
+                10,
+                0
+            )),
+            Command::parse(&args("tx send alice bob 10")).unwrap()
+        );
+        assert_eq!(
+            Some(Command::Send(
+                String::from("alice"),
+                String::from("bob"),
+                10,
+                2
+            )),
+            Command::parse(&args("tx send alice bob 10 2")).unwrap()
+        );
+        assert_eq!(
+            Some(Command::Show(DataFile::Blockchain)),
+            Command::parse(&args("show blocks")).unwrap()
+        );
+        assert_eq!(
+            Some(Command::Block(3)),
+            Command::parse(&args("block 3")).unwrap()
+        );
+        assert_eq!(
+            Some(Command::Supply),
+            Command::parse(&args("supply")).unwrap()
+        );
+        assert_eq!(
+            Some(Command::Init(true)),
+            Command::parse(&args("init --preserve-accounts")).unwrap()
+        );
+        assert!(Command::parse(&args("tx send alice bob ten")).is_err());
+        assert!(Command::parse(&args("tx send alice bob 10 two")).is_err());
+        assert!(Command::parse(&args("wallet create")).is_err());
+        assert!(Command::parse(&args("block -1")).is_err());
+        assert!(Command::parse(&args("show everything")).is_err());
+    }
+
+    #[test]
+    fn test_parse_output_format() {
+        assert_eq!(
+            Cli {
+                command: Some(Command::Show(DataFile::Wallets)),
+                output: OutputFormat::Table,
+                data_dir: None,
+                chain: None,
+            },
+            Cli::parse(&args("show --output table wallets")).unwrap()
+        );
+        assert_eq!(
+            Cli {
+                command: None,
+                output: OutputFormat::Json,
+                data_dir: None,
+                chain: None,
+            },
+            Cli::parse(&args("--output=json")).unwrap()
+        );
+        assert_eq!(
+            Cli {
+                command: Some(Command::Verify),
+                output: OutputFormat::Pretty,
+                data_dir: Some(String::from("/tmp/chains")),
+                chain: Some(String::from("testnet-a")),
+            },
+            Cli::parse(&args("--data-dir /tmp/chains verify --chain=testnet-a")).unwrap()
+        );
+        assert!(Cli::parse(&args("verify --chain")).is_err());
+        assert!(Cli::parse(&args("verify --output")).is_err());
+        assert!(Cli::parse(&args("verify --output yaml")).is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        let mut chain = Mockchain::in_memory().unwrap();
+        for line in ["wallet create alice", "wallet create bob", "mine alice"] {
+            let command = Command::parse(&args(line)).unwrap().unwrap();
+            assert!(command.run(&mut chain, OutputFormat::Json).unwrap());
+        }
+        assert!(Command::Verify
+            .run(&mut chain, OutputFormat::Table)
+            .unwrap());
+        assert!(matches!(
+            Command::Send(String::from("alice"), String::from("bob"), 10, 0)
+                .run(&mut chain, OutputFormat::Json),
+            Err(MockchainError::InsufficientFunds(_, 10))
+        ));
+    }
+}
@@ -0,0 +1,477 @@
+/*
+    Chain configuration read from config.json
+*/
+
+// std library
+use std::fs;
+use std::path::Path;
+
+// 3rd party crates
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::from_str;
+
+// imports
+use super::{
+    block::Block,
+    error::MockchainError,
+    genesis::GenesisSpec,
+    log::{Log, LogLevel},
+};
+
+// block hashes are hex strings so every leading zero
+// multiplies the expected mining work by 16, the 2^32
+// tries of the cap fit easily in the u64 header nonce
+pub const MAX_DIFFICULTY: u32 = 8;
+
+/// Settings for adjusting the mining difficulty from
+/// the timestamps of recent blocks
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// target_block_time: i64 -> seconds a block should take to mine on average
+/// window: usize          -> number of blocks between adjustments
+/// min_difficulty: u32    -> lowest difficulty retargeting can reach
+/// max_difficulty: u32    -> highest difficulty retargeting can reach
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetargetConfig {
+    pub target_block_time: i64,
+    pub window: usize,
+    pub min_difficulty: u32,
+    pub max_difficulty: u32,
+}
+
+impl Default for RetargetConfig {
+    fn default() -> RetargetConfig {
+        RetargetConfig {
+            target_block_time: 60,
+            window: 10,
+            min_difficulty: 1,
+            max_difficulty: 4,
+        }
+    }
+}
+
+/// Monetary policy deciding the subsidy paid for
+/// mining each block. The subsidy starts at the
+/// initial reward, halves every halving interval and
+/// stops once the maximum supply has been issued
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// initial_reward: i32    -> subsidy of the first mined block
+/// halving_interval: u64  -> blocks between halvings, 0 never halves
+/// max_supply: i64        -> most tokens subsidies can ever issue
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct MonetaryPolicy {
+    pub initial_reward: i32,
+    pub halving_interval: u64,
+    pub max_supply: i64,
+}
+
+impl Default for MonetaryPolicy {
+    fn default() -> MonetaryPolicy {
+        MonetaryPolicy {
+            initial_reward: 50,
+            halving_interval: 210,
+            max_supply: 21_000,
+        }
+    }
+}
+
+impl MonetaryPolicy {
+    /// Gets the total issued by the subsidies of every
+    /// block up to and including a height, capped at the
+    /// maximum supply. The genesis block at height 0
+    /// issues nothing
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// height: u64 -> height of the last block counted
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// i64
+    /// ```
+    pub fn issued(&self, height: u64) -> i64 {
+        let mut issued: i64 = 0;
+        let mut start = 1;
+        let mut reward = self.initial_reward as i64;
+        // add up whole eras of equal subsidies
+        while start <= height && reward > 0 && issued < self.max_supply {
+            let end = match self.halving_interval {
+                0 => height,
+                interval => height.min(start.saturating_add(interval - 1)),
+            };
+            // an era reaching the highest heights holds more blocks than an i64
+            let blocks = i64::try_from(end - start).map_or(i64::MAX, |n| n.saturating_add(1));
+            issued = issued.saturating_add(reward.saturating_mul(blocks));
+            start = match end.checked_add(1) {
+                Some(next) => next,
+                None => break,
+            };
+            reward /= 2;
+        }
+        issued.min(self.max_supply)
+    }
+
+    /// Gets the subsidy paid for mining the block at a
+    /// height
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// height: u64 -> height of the mined block
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// i32
+    /// ```
+    pub fn subsidy(&self, height: u64) -> i32 {
+        if height == 0 {
+            return 0;
+        }
+        // the issued supply never falls as the height grows, so the
+        // difference fits in an i32 unless the supply saturated
+        i32::try_from(self.issued(height).saturating_sub(self.issued(height - 1))).unwrap_or(0)
+    }
+}
+
+/// Configuration of a chain. Every field is optional
+/// in config.json and falls back to its default
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// difficulty: u32                  -> leading zeros a block hash must start with
+/// retarget: Option<RetargetConfig> -> difficulty adjustment, disabled when None
+/// plaintext_keys: bool             -> store new private keys unencrypted
+/// max_block_size: usize            -> bytes of transactions a block can hold, the coinbase aside
+/// monetary_policy: MonetaryPolicy  -> subsidy schedule of mining rewards
+/// coinbase_maturity: u64           -> blocks mined on top of a coinbase before it can be spent
+/// genesis: GenesisSpec             -> spec of the genesis block, read from genesis.json
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ChainConfig {
+    pub difficulty: u32,
+    pub retarget: Option<RetargetConfig>,
+    pub plaintext_keys: bool,
+    pub max_block_size: usize,
+    pub monetary_policy: MonetaryPolicy,
+    pub coinbase_maturity: u64,
+    #[serde(skip)]
+    pub genesis: GenesisSpec,
+}
+
+impl Default for ChainConfig {
+    fn default() -> ChainConfig {
+        ChainConfig {
+            difficulty: 2,
+            retarget: None,
+            plaintext_keys: false,
+            max_block_size: 8192,
+            monetary_policy: MonetaryPolicy::default(),
+            coinbase_maturity: 2,
+            genesis: GenesisSpec::default(),
+        }
+    }
+}
+
+impl ChainConfig {
+    /// Loads the chain configuration from a JSON file.
+    /// The default configuration is used when the file
+    /// does not exist
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path -> path to config.json
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<ChainConfig, MockchainError>
+    /// ```
+    pub fn load(path: &Path) -> Result<ChainConfig, MockchainError> {
+        if !path.exists() {
+            return Ok(ChainConfig::default());
+        }
+        let json_str = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                return Err(MockchainError::Io(format!(
+                    "Error reading config file at {:?}: {}",
+                    path, e
+                )))
+            }
+        };
+        let config: ChainConfig = match from_str(&json_str) {
+            Ok(config) => config,
+            Err(e) => {
+                return Err(MockchainError::Config(format!(
+                    "Poorly formatted JSON found in {:?}: {}",
+                    path, e
+                )))
+            }
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks the configured difficulties can be mined
+    /// and the genesis spec can be built
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    pub fn validate(&self) -> Result<(), MockchainError> {
+        if self.difficulty > MAX_DIFFICULTY {
+            return Err(MockchainError::Config(format!(
+                "difficulty must be at most {}; given value: {}",
+                MAX_DIFFICULTY, self.difficulty
+            )));
+        }
+        let policy = &self.monetary_policy;
+        if policy.initial_reward < 0 || policy.max_supply < 0 {
+            return Err(MockchainError::Config(
+                "monetary_policy initial_reward and max_supply cannot be negative".to_string(),
+            ));
+        }
+        if self.max_block_size == 0 {
+            return Err(MockchainError::Config(
+                "max_block_size must be greater than 0".to_string(),
+            ));
+        }
+        if let Some(retarget) = &self.retarget {
+            if retarget.window == 0 || retarget.target_block_time <= 0 {
+                return Err(MockchainError::Config(
+                    "retarget window and target_block_time must be greater than 0".to_string(),
+                ));
+            }
+            if retarget.min_difficulty > retarget.max_difficulty
+                || retarget.max_difficulty > MAX_DIFFICULTY
+            {
+                return Err(MockchainError::Config(format!(
+                    "retarget difficulties must satisfy min <= max <= {}",
+                    MAX_DIFFICULTY
+                )));
+            }
+        }
+        self.genesis.validate()
+    }
+
+    /// Works out the difficulty of the next block. With
+    /// retargeting enabled the difficulty of the last
+    /// block is raised or lowered by 1 at the end of each
+    /// window when the average block time is less than
+    /// half or more than double the target block time
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// blocks: &[Block] -> blocks currently in the chain, genesis first
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// u32
+    /// ```
+    pub fn next_difficulty(&self, blocks: &[Block]) -> u32 {
+        let retarget = match &self.retarget {
+            Some(retarget) => retarget,
+            None => return self.difficulty,
+        };
+        // the genesis block is not mined so it has no difficulty to carry over
+        let current = match blocks.last() {
+            Some(block) if blocks.len() > 1 => block.header.difficulty,
+            _ => self.difficulty,
+        };
+        let current = current.clamp(retarget.min_difficulty, retarget.max_difficulty);
+        if blocks.len() <= retarget.window || !blocks.len().is_multiple_of(retarget.window) {
+            return current;
+        }
+
+        let recent = &blocks[blocks.len() - retarget.window - 1..];
+        let (first, last) = (
+            &recent[0].header.timestamp,
+            &recent[retarget.window].header.timestamp,
+        );
+        let elapsed = match (
+            DateTime::parse_from_rfc3339(first),
+            DateTime::parse_from_rfc3339(last),
+        ) {
+            (Ok(first), Ok(last)) => (last - first).num_seconds(),
+            _ => return current,
+        };
+        let average = elapsed / retarget.window as i64;
+
+        let next = if average * 2 < retarget.target_block_time {
+            (current + 1).min(retarget.max_difficulty)
+        } else if average > retarget.target_block_time * 2 {
+            current.saturating_sub(1).max(retarget.min_difficulty)
+        } else {
+            current
+        };
+        if next != current {
+            Log::new(
+                LogLevel::INFO,
+                34,
+                Some(vec![
+                    current.to_string(),
+                    next.to_string(),
+                    average.to_string(),
+                    retarget.window.to_string(),
+                ]),
+            );
+        }
+        next
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_config {
+    use super::*;
+
+    use chrono::{Duration, Utc};
+
+    use crate::mods::block::{BlockHeader, BLOCK_VERSION};
+
+    fn blocks(count: usize, spacing: i64, difficulty: u32) -> Vec<Block> {
+        let start = Utc::now();
+        (0..count)
+            .map(|i| Block {
+                header: BlockHeader {
+                    version: BLOCK_VERSION,
+                    height: i as u64,
+                    previous_hash: String::new(),
+                    merkle_root: String::from("None"),
+                    timestamp: (start + Duration::seconds(spacing * i as i64)).to_rfc3339(),
+                    difficulty,
+                    nonce: 0,
+                },
+                hash: String::new(),
+                transactions: Vec::new(),
+            })
+            .collect()
+    }
+
+    fn retargeting() -> ChainConfig {
+        ChainConfig {
+            difficulty: 2,
+            retarget: Some(RetargetConfig {
+                target_block_time: 60,
+                window: 4,
+                min_difficulty: 1,
+                max_difficulty: 3,
+            }),
+            ..ChainConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_fixed_difficulty() {
+        let config = ChainConfig {
+            difficulty: 3,
+            retarget: None,
+            ..ChainConfig::default()
+        };
+        assert_eq!(3, config.next_difficulty(&blocks(8, 1, 1)));
+    }
+
+    #[test]
+    fn test_retarget() {
+        let config = retargeting();
+        // fast blocks raise the difficulty, slow ones lower it
+        assert_eq!(3, config.next_difficulty(&blocks(8, 1, 2)));
+        assert_eq!(1, config.next_difficulty(&blocks(8, 600, 2)));
+        assert_eq!(2, config.next_difficulty(&blocks(8, 60, 2)));
+        // only adjusted at the end of a window and within bounds
+        assert_eq!(2, config.next_difficulty(&blocks(7, 1, 2)));
+        assert_eq!(3, config.next_difficulty(&blocks(8, 1, 3)));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = retargeting();
+        assert!(config.validate().is_ok());
+        config.difficulty = MAX_DIFFICULTY + 1;
+        assert!(matches!(config.validate(), Err(MockchainError::Config(_))));
+    }
+
+    #[test]
+    fn test_monetary_policy() {
+        let policy = MonetaryPolicy {
+            initial_reward: 50,
+            halving_interval: 2,
+            max_supply: 140,
+        };
+        // 50, 50, 25, then only 15 of the next 25 fits under the cap
+        let subsidies: Vec<i32> = (0..7).map(|h| policy.subsidy(h)).collect();
+        assert_eq!(vec![0, 50, 50, 25, 15, 0, 0], subsidies);
+        assert_eq!(140, policy.issued(1000));
+
+        // the default schedule issues 50 a block for 210 blocks and tends to 21000
+        let policy = MonetaryPolicy::default();
+        assert_eq!(50, policy.subsidy(210));
+        assert_eq!(25, policy.subsidy(211));
+        assert_eq!(10_500, policy.issued(210));
+        assert!(policy.issued(u64::MAX) <= 21_000);
+
+        // a forged height at the end of an endless era cannot overflow
+        let policy = MonetaryPolicy {
+            initial_reward: 50,
+            halving_interval: 0,
+            max_supply: i64::MAX,
+        };
+        assert_eq!(i64::MAX, policy.issued(u64::MAX));
+        assert_eq!(0, policy.subsidy(u64::MAX));
+        assert_eq!(50, policy.subsidy(1 << 50));
+    }
+}
@@ -1,5 +1,4 @@
 use std::fmt::Debug;
-use std::path::Path;
 use std::str;
 
 // 3rd party crates
@@ -12,20 +11,20 @@ use p256::{
     SecretKey,
 };
 use rand_core::OsRng;
-use rs_merkle::{algorithms::Sha256, Hasher, MerkleTree};
-use serde::Serialize;
+use rs_merkle::{algorithms::Sha256, Hasher, MerkleProof, MerkleTree};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
 use sha256::digest;
 
 // imports
 use super::{
-    file::FileOps,
-    log::{Log, LogLevel},
+    block::{Block, BlockHeader},
+    canonical::canonical_json,
+    error::MockchainError,
+    keystore::KeyCrypto,
+    store::ChainStore,
+    transaction::Transaction,
 };
-use crate::KEYPAIRS_PATH;
-
-// hash delimiter
-#[allow(dead_code)]
-pub const DELIMITER: &str = "-%-";
 
 /// Defines a KeyPair object for storing private and public keys
 ///
@@ -36,18 +35,25 @@ pub const DELIMITER: &str = "-%-";
 /// ```
 /// name: String
 /// public_key: String
-/// private_key: String
+/// private_key: String        -> hex private key, empty when encrypted
+/// crypto: Option<KeyCrypto>  -> encrypted private key
+/// path: Option<String>       -> derivation path when restored from a seed phrase
 /// ```
 ///
 /// # Derives
 /// ```
-/// serde::Serialize, Debug
+/// serde::Serialize, serde::Deserialize, Debug, Clone
 /// ```
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct KeyPair {
     pub name: String,
     pub public_key: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub private_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crypto: Option<KeyCrypto>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
 }
 
 impl KeyPair {
@@ -67,53 +73,71 @@ impl KeyPair {
     /// KeyPair
     /// ```
     pub fn generate(name: String) -> KeyPair {
+        KeyPair::from_secret(name, &SecretKey::random(&mut OsRng))
+    }
+
+    /// Creates a key pair from an existing private key,
+    /// such as one derived from a seed phrase
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: String        -> name of the account for this key pair
+    /// secret: &SecretKey  -> private key
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// KeyPair
+    /// ```
+    pub fn from_secret(name: String, secret: &SecretKey) -> KeyPair {
         // private key first
-        let secret = SecretKey::random(&mut OsRng);
-        let private_key = encode(&secret.to_bytes());
+        let private_key = encode(secret.to_bytes());
         // then public key
         let public_key = encode(secret.public_key().to_sec1_bytes());
-        let key_pair = KeyPair {
+        KeyPair {
             name,
             public_key,
             private_key,
-        };
-        key_pair
+            crypto: None,
+            path: None,
+        }
     }
 
-    /// Gets a key from keypairs.json file
+    /// Gets a key from keypairs.json file. Encrypted
+    /// private keys can only be read through a Keystore
     ///
     /// # Visibility
     /// public
     ///
     /// # Args
     /// ```
-    /// name: String -> name of account to get key from
-    /// key: String  -> ["public" | "private"]
+    /// store: &dyn ChainStore -> storage backend holding the key pairs
+    /// name: &str             -> name of account to get key from
+    /// key: &str              -> ["public" | "private"]
     /// ```
     ///
     /// # Returns
     /// ```
-    /// String
+    /// Result<String, MockchainError>
     /// ```
-    pub fn get_key(name: String, key: String) -> String {
-        let mut base_data = FileOps::parse(KEYPAIRS_PATH.as_path());
-        let keypairs = match base_data["keypairs"].as_array_mut() {
-            Some(data) => data,
-            None => {
-                Log::new_panic(LogLevel::ERROR, 2, Some(vec!["keypairs.json".to_string()]));
-                panic!("Failed to read keypairs.json, has the data been modified or the file moved or deleted?");
-            }
-        };
-        for k in keypairs {
-            if k["name"] == name {
-                let mut id = String::from("");
-                id.push_str(key.to_owned().as_str());
-                id.push_str("_key");
-                // replace " in returned Value with empty string
-                return k[id].to_string().replace("\"", "");
-            }
+    pub fn get_key(
+        store: &dyn ChainStore,
+        name: &str,
+        key: &str,
+    ) -> Result<String, MockchainError> {
+        match store.keypairs()?.into_iter().find(|k| k.name == name) {
+            Some(key_pair) => match key {
+                "private" if key_pair.crypto.is_some() => {
+                    Err(MockchainError::PasswordRequired(name.to_string()))
+                }
+                "private" => Ok(key_pair.private_key),
+                _ => Ok(key_pair.public_key),
+            },
+            None => Err(MockchainError::KeyPairNotFound(name.to_string())),
         }
-        format!("No key pair found under {name}").to_string()
     }
 
     /// Signs a transaction on the blockchain using
@@ -124,37 +148,35 @@ impl KeyPair {
     ///
     /// # Args
     /// ```
-    /// hash: String        -> transaction hash to sign
-    /// private_key: String -> private key to sign with
+    /// hash: &str       -> transaction hash to sign
+    /// private_key: &str -> private key to sign with
     /// ```
     ///
     /// # Returns
     /// ```
-    /// (String, String)
+    /// Result<String, MockchainError>
     /// ```
-    pub fn sign(hash: &String, private_key: String) -> (String, String) {
-        let key_bytes = match decode(&private_key) {
+    pub fn sign(hash: &str, private_key: &str) -> Result<String, MockchainError> {
+        let key_bytes = match decode(private_key) {
             Ok(key) => key,
             Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 3, Some(vec![private_key.clone()]));
-                panic!(
+                return Err(MockchainError::Crypto(format!(
                     "Failed to decode private key while signing transaction: {}",
                     e
-                );
+                )));
             }
         };
         let signing_key = match SigningKey::from_slice(key_bytes.as_slice()) {
             Ok(key) => key,
             Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 4, None);
-                panic!(
+                return Err(MockchainError::Crypto(format!(
                     "Failed to decode signing key from bytes while signing transaction: {}",
                     e
-                );
+                )));
             }
         };
-        let signature: Signature = signing_key.sign(&hash.as_bytes());
-        (encode(signature.to_bytes()), encode(signing_key.to_bytes()))
+        let signature: Signature = signing_key.sign(hash.as_bytes());
+        Ok(encode(signature.to_bytes()))
     }
 
     /// Verifies a transaction on the blockchain using
@@ -165,191 +187,358 @@ impl KeyPair {
     ///
     /// # Args
     /// ```
-    /// signature: String -> signature to verify
-    /// hash: String      -> transaction hash to verify
+    /// signature: &Signature        -> signature to verify
+    /// verifying_key: &VerifyingKey -> public key of the signer
+    /// hash: &str                   -> transaction hash to verify
     /// ```
     ///
     /// # Returns
     /// ```
     /// bool
     /// ```
-    pub fn verify(signature: Signature, signing_key: SigningKey, hash: String) -> bool {
-        let verifying_key = VerifyingKey::from(&signing_key);
-        let verified = match verifying_key.verify(&hash.as_bytes(), &signature) {
-            Ok(_res) => true,
-            Err(_) => false,
-        };
-        verified
+    pub fn verify(signature: &Signature, verifying_key: &VerifyingKey, hash: &str) -> bool {
+        verifying_key.verify(hash.as_bytes(), signature).is_ok()
     }
 
-    /// Extract Signature and SigningKey objects from encoded
-    /// hex strings
+    /// Extract Signature and VerifyingKey objects from encoded
+    /// hex strings. The public key is expected to be the hex
+    /// encoded SEC1 bytes stored as a wallet address
     ///
     /// # Visibility
     /// public
     ///
     /// # Args
     /// ```
-    /// signature: String   -> hex encoded Signature object
-    /// signing_key: String -> hex encoded SigningKey object
+    /// signature: &str  -> hex encoded Signature object
+    /// public_key: &str -> hex encoded SEC1 public key
     /// ```
     ///
     /// # Returns
     /// ```
-    /// (Signature, SigningKey)
+    /// Result<(Signature, VerifyingKey), MockchainError>
     /// ```
-    pub fn extract(signature: String, signing_key: String) -> (Signature, SigningKey) {
-        // decode and extract Signature and SigningKey objects
-        let sig = match decode(signature.clone()) {
-            Ok(bytes) => match Signature::from_slice(bytes.as_slice()) {
-                Ok(s) => s,
-                Err(e) => {
-                    Log::new_panic(LogLevel::ERROR, 5, Some(vec![signature]));
-                    panic!("Cannot decode signature: {}", e);
-                }
-            },
-            Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 5, Some(vec![signature]));
-                panic!("Cannot decode signature: {}", e);
+    pub fn extract(
+        signature: &str,
+        public_key: &str,
+    ) -> Result<(Signature, VerifyingKey), MockchainError> {
+        // decode and extract Signature and VerifyingKey objects
+        let sig = match decode(signature) {
+            Ok(bytes) => Signature::from_slice(bytes.as_slice()).ok(),
+            Err(_) => None,
+        };
+        let sig = match sig {
+            Some(s) => s,
+            None => {
+                return Err(MockchainError::Crypto(format!(
+                    "Cannot decode signature: {}",
+                    signature
+                )));
             }
         };
-        let sign = match decode(signing_key.clone()) {
-            Ok(bytes) => match SigningKey::from_slice(bytes.as_slice()) {
-                Ok(s) => s,
-                Err(e) => {
-                    Log::new_panic(LogLevel::ERROR, 6, Some(vec![signing_key]));
-                    panic!("Cannot decode signing key: {}", e);
-                }
-            },
-            Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 6, Some(vec![signing_key]));
-                panic!("Cannot decode signing key: {}", e);
+        let key = match decode(public_key) {
+            Ok(bytes) => VerifyingKey::from_sec1_bytes(bytes.as_slice()).ok(),
+            Err(_) => None,
+        };
+        let key = match key {
+            Some(k) => k,
+            None => {
+                return Err(MockchainError::Crypto(format!(
+                    "Cannot decode verifying key: {}",
+                    public_key
+                )));
             }
         };
-        (sig, sign)
+        Ok((sig, key))
     }
 }
 
-/// Creates a SHA256 hash of the components of
-/// a block. The delimiter aims to prevent an
-/// attack where the string components of the
-/// hash are combined in a different segments
-/// e.g.
+/// Creates a SHA256 hash of the header of a block.
+/// The transactions are not hashed directly, they are
+/// bound to the header through its merkle root. The
+/// header is hashed in its canonical JSON encoding so
+/// the hash does not depend on how blockchain.json is
+/// formatted
+///
+/// # Visibility
+/// public
 ///
+/// # Args
 /// ```
-/// digest("abc" + "def") == digest("ab" + "cdef")
+/// header: &BlockHeader -> header of the block
 /// ```
 ///
+/// # Returns
+/// ```
+/// String
+/// ```
+pub fn hash_block(header: &BlockHeader) -> String {
+    digest(canonical_json(&json!(header)))
+}
+
+/// Creates a SHA256 hash of a chain id, used as the
+/// previous hash of the genesis block so chains with
+/// different ids never share a genesis block
+///
 /// # Visibility
 /// public
 ///
 /// # Args
 /// ```
-/// nonce: &String        -> block nonce value  
-/// prev_hash: &String    -> hash of the previous block
-/// transactions: &String -> JSON serialized String of transactions
+/// chain_id: &str -> id of the chain
 /// ```
 ///
 /// # Returns
 /// ```
 /// String
 /// ```
-pub fn hash_block(nonce: &String, prev_hash: &String, transactions: &String) -> String {
-    let mut values: String = String::from("");
-    values.push_str(nonce.as_str());
-    values.push_str(DELIMITER);
-    values.push_str(prev_hash.as_str());
-    values.push_str(DELIMITER);
-    values.push_str(transactions.as_str());
-    digest(values)
+pub fn hash_chain_id(chain_id: &str) -> String {
+    digest(canonical_json(&json!({ "chain_id": chain_id })))
 }
 
-/// Creates a SHA256 hash of the components of
-/// a transaction. The delimiter aims to prevent an
-/// attack where the string components of the
-/// hash are combined in a different segments
-/// e.g.
+/// Checks a block hash starts with the number of
+/// leading zeros required by a difficulty
 ///
+/// # Visibility
+/// public
+///
+/// # Args
 /// ```
-/// digest("abc" + "def") == digest("ab" + "cdef")
+/// hash: &str      -> block hash to check
+/// difficulty: u32 -> required number of leading zeros
 /// ```
 ///
+/// # Returns
+/// ```
+/// bool
+/// ```
+pub fn meets_difficulty(hash: &str, difficulty: u32) -> bool {
+    hash.starts_with(&"0".repeat(difficulty as usize))
+}
+
+/// Creates a SHA256 hash of the components of
+/// a transaction in their canonical JSON encoding.
+/// Every value is a typed JSON field, so components
+/// cannot be shifted between fields to give the same
+/// hash
+///
 /// # Visibility
 /// public
 ///
 /// # Args
 /// ```
-/// from_address: &String -> the senders private key
-/// to_address: &String   -> the recipients public key
-/// amount: &String       -> amount being sent
+/// from_address: &str -> address of the sender
+/// to_address: &str   -> address of the recipient
+/// amount: i32        -> amount being sent
+/// fee: i32           -> fee paid to the miner
+/// nonce: u64         -> senders transaction nonce
+/// timestamp: &str    -> time the transaction was created
 /// ```
 ///
 /// # Returns
 /// ```
 /// String
 /// ```
-pub fn hash_transaction(from_address: &String, to_address: &String, amount: &String) -> String {
-    let mut values: String = String::from("");
-    values.push_str(from_address.as_str());
-    values.push_str(DELIMITER);
-    values.push_str(to_address.as_str());
-    values.push_str(DELIMITER);
-    values.push_str(amount.as_str());
-    digest(values)
+pub fn hash_transaction(
+    from_address: &str,
+    to_address: &str,
+    amount: i32,
+    fee: i32,
+    nonce: u64,
+    timestamp: &str,
+) -> String {
+    digest(canonical_json(&json!({
+        "from_address": from_address,
+        "to_address": to_address,
+        "amount": amount,
+        "fee": fee,
+        "nonce": nonce,
+        "timestamp": timestamp,
+    })))
+}
+
+/// Proof that a transaction is included in a block,
+/// made of the sibling hashes needed to rebuild the
+/// merkle root from the transaction
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// block_hash: String        -> hash of the Block holding the transaction
+/// transaction: Transaction  -> the proven Transaction
+/// index: usize              -> position of the transaction in the Block
+/// total_leaves: usize       -> number of transactions in the Block
+/// proof_hashes: Vec<String> -> hex encoded sibling hashes
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug, Clone
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InclusionProof {
+    pub block_hash: String,
+    pub transaction: Transaction,
+    pub index: usize,
+    pub total_leaves: usize,
+    pub proof_hashes: Vec<String>,
+}
+
+impl InclusionProof {
+    /// Checks the proof rebuilds a merkle root
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// merkle_root: &str -> hex encoded root to check the proof against
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    pub fn verify(&self, merkle_root: &str) -> bool {
+        let root = match decode_hash(merkle_root) {
+            Some(root) => root,
+            None => return false,
+        };
+        let mut proof_hashes = Vec::with_capacity(self.proof_hashes.len());
+        for hash in self.proof_hashes.iter() {
+            match decode_hash(hash) {
+                Some(hash) => proof_hashes.push(hash),
+                None => return false,
+            }
+        }
+        let leaf = merkle_leaf(&self.transaction);
+        MerkleProof::<Sha256>::new(proof_hashes).verify(
+            root,
+            &[self.index],
+            &[leaf],
+            self.total_leaves,
+        )
+    }
+}
+
+/// Decodes a hex encoded SHA256 hash
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// hash: &str -> hex encoded hash
+/// ```
+///
+/// # Returns
+/// ```
+/// Option<[u8; 32]>
+/// ```
+fn decode_hash(hash: &str) -> Option<[u8; 32]> {
+    decode(hash).ok()?.try_into().ok()
+}
+
+/// Hashes a transaction into a merkle leaf. The whole
+/// transaction is hashed in its canonical JSON
+/// encoding, so the public key and signature are bound
+/// to the block as well as the signed components
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// transaction: &Transaction -> transaction to hash
+/// ```
+///
+/// # Returns
+/// ```
+/// [u8; 32]
+/// ```
+fn merkle_leaf(transaction: &Transaction) -> [u8; 32] {
+    Sha256::hash(canonical_json(&json!(transaction)).as_bytes())
+}
+
+/// Builds the Merkle Tree of a set of transactions
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// transactions: &[Transaction] -> transactions in the block
+/// ```
+///
+/// # Returns
+/// ```
+/// MerkleTree<Sha256>
+/// ```
+fn merkle_tree(transactions: &[Transaction]) -> MerkleTree<Sha256> {
+    let hashes: Vec<[u8; 32]> = transactions.iter().map(merkle_leaf).collect();
+    MerkleTree::<Sha256>::from_leaves(&hashes)
 }
 
 /// Creates a Merkle Root by hashing all the transactions
-/// that are going to be added to a block
+/// in a block
 ///
 /// # Visibility
 /// public
 ///
 /// # Args
 /// ```
-/// path: &Path -> path to read transactions from
+/// transactions: &[Transaction] -> transactions in the block
 /// ```
 ///
 /// # Returns
 /// ```
 /// String
 /// ```
-pub fn get_merkle_root(path: &Path) -> String {
-    let mut base_data = FileOps::parse(path);
-    let transactions = match base_data["transactions"].as_array_mut() {
-        Some(data) => data,
-        None => {
-            Log::new_panic(
-                LogLevel::ERROR,
-                2,
-                Some(vec!["transactions.json".to_string()]),
-            );
-            panic!("Failed to read transactions.json, has the data been modified or the file moved or deleted?");
-        }
-    };
-    if transactions.len() > 0 {
-        let mut hashes = Vec::new();
-        for t in transactions {
-            hashes.push(Sha256::hash(t["hash"].to_string().as_bytes()));
-        }
-        let merkle_tree = MerkleTree::<Sha256>::from_leaves(&hashes);
-        if let Some(root) = merkle_tree.root_hex() {
-            root
-        } else {
-            String::from("None")
-        }
-    } else {
-        String::from("None")
+pub fn get_merkle_root(transactions: &[Transaction]) -> String {
+    match merkle_tree(transactions).root_hex() {
+        Some(root) => root,
+        None => String::from("None"),
     }
 }
 
+/// Creates an inclusion proof for a transaction in
+/// a block
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// block: &Block            -> Block holding the transaction
+/// transaction_hash: &str   -> hash of the transaction to prove
+/// ```
+///
+/// # Returns
+/// ```
+/// Option<InclusionProof>
+/// ```
+pub fn get_inclusion_proof(block: &Block, transaction_hash: &str) -> Option<InclusionProof> {
+    let index = block
+        .transactions
+        .iter()
+        .position(|t| t.hash == transaction_hash)?;
+    let transaction = block.transactions[index].clone();
+    let proof = merkle_tree(&block.transactions).proof(&[index]);
+    Some(InclusionProof {
+        block_hash: block.hash.clone(),
+        transaction,
+        index,
+        total_leaves: block.transactions.len(),
+        proof_hashes: proof.proof_hashes_hex(),
+    })
+}
+
 // Testing
 #[cfg(test)]
 mod test_crypto {
     use super::*;
 
-    use crate::mods::transaction::Transaction;
-    use serde_json::to_string;
-
     #[test]
     fn test_sign_extract_verify() {
         // test hash and private key
@@ -357,61 +546,124 @@ mod test_crypto {
         let test_private_key =
             String::from("4cae0e746defac95cba2dd5cdb440bb54d102713aeedcad19a483851c0a5ef21");
 
-        // get a signature and signing key by signing the test hash
-        let (sig, key) = KeyPair::sign(&test_hash, test_private_key);
+        // derive the public key the way KeyPair::generate does
+        let secret = match SecretKey::from_slice(&decode(&test_private_key).unwrap()) {
+            Ok(key) => key,
+            Err(e) => panic!("Error decoding test private key: {}", e),
+        };
+        let test_public_key = encode(secret.public_key().to_sec1_bytes());
+
+        // get a signature by signing the test hash
+        let sig = KeyPair::sign(&test_hash, &test_private_key).unwrap();
 
-        // extract the Signature and SigningKey
-        let (signature, signing_key) = KeyPair::extract(sig, key);
+        // extract the Signature and VerifyingKey
+        let (signature, verifying_key) = KeyPair::extract(&sig, &test_public_key).unwrap();
 
         // assert verification of hash signature
-        assert!(KeyPair::verify(signature, signing_key, test_hash));
+        assert!(KeyPair::verify(&signature, &verifying_key, &test_hash));
     }
 
     #[test]
-    fn test_hash_block() {
-        let transactions: [Transaction; 1] = [Transaction {
-            hash: String::from("2".repeat(64)),
-            from_address: String::from("2".repeat(130)),
-            to_address: String::from("3".repeat(130)),
-            amount: 10,
-            signature: String::from("4".repeat(128)),
-        }];
+    fn test_verify_rejects_other_public_key() {
+        let test_hash = "0".repeat(64);
+        let test_private_key =
+            String::from("4cae0e746defac95cba2dd5cdb440bb54d102713aeedcad19a483851c0a5ef21");
+        let sig = KeyPair::sign(&test_hash, &test_private_key).unwrap();
+
+        // a signature must not verify against a key that did not produce it
+        let other = KeyPair::generate(String::from("other"));
+        let (signature, verifying_key) = KeyPair::extract(&sig, &other.public_key).unwrap();
+
+        assert!(!KeyPair::verify(&signature, &verifying_key, &test_hash));
+    }
 
-        let transaction_string = match to_string(&transactions) {
-            Ok(val) => val,
-            Err(e) => panic!(
-                "Error parsing transaction struct to serde_json Value::String: {}",
-                e
-            ),
+    #[test]
+    fn test_hash_block() {
+        let header = BlockHeader {
+            version: 1,
+            height: 3,
+            previous_hash: "1".repeat(64),
+            merkle_root: "2".repeat(64),
+            timestamp: String::from("2024-01-01T00:00:00+00:00"),
+            difficulty: 2,
+            nonce: 165,
         };
 
         assert_eq!(
-            "c47b8a851113808578895f8f783961e38d9a0c481f1f921d90ac9c4905eca797",
-            hash_block(
-                &String::from("165"),
-                &String::from("1").repeat(64),
-                &transaction_string,
-            )
+            "a777c87c90b2c2b0be41226b8d70ccfdadd45e5e1575c4a375a0ed0ca192b5cd",
+            hash_block(&header)
         );
+        // every header field is covered by the hash
+        let mut other = header.clone();
+        other.height = 4;
+        assert_ne!(hash_block(&header), hash_block(&other));
     }
 
     #[test]
     fn test_hash_transactions() {
         let transactions: [Transaction; 1] = [Transaction {
-            hash: String::from("2".repeat(64)),
-            from_address: String::from("2".repeat(130)),
-            to_address: String::from("3".repeat(130)),
+            hash: "2".repeat(64),
+            from_address: "2".repeat(130),
+            to_address: "3".repeat(130),
+            public_key: "2".repeat(130),
             amount: 10,
-            signature: String::from("4".repeat(128)),
+            fee: 0,
+            nonce: 0,
+            timestamp: String::from("2024-01-01T00:00:00+00:00"),
+            signature: "4".repeat(128),
         }];
 
         assert_eq!(
-            "72426b1405464c9f600c859b8c4a9d9097e3a2f60850d8fbeb89c7985507bbc3",
+            "372900a0a8c3c8f3df8abd2a9326c7fb2e5d17899fde98460026cbad01cfc75c",
             hash_transaction(
                 &transactions[0].from_address,
                 &transactions[0].to_address,
-                &transactions[0].amount.to_string()
+                transactions[0].amount,
+                transactions[0].fee,
+                transactions[0].nonce,
+                &transactions[0].timestamp
             )
         );
     }
+
+    #[test]
+    fn test_inclusion_proof() {
+        let transactions: Vec<Transaction> = (0..5)
+            .map(|i| Transaction {
+                hash: hash_transaction("a", "b", 1, 0, i, ""),
+                from_address: String::from("a"),
+                to_address: String::from("b"),
+                public_key: String::new(),
+                amount: 1,
+                fee: 0,
+                nonce: i,
+                timestamp: String::new(),
+                signature: String::new(),
+            })
+            .collect();
+        let merkle_root = get_merkle_root(&transactions);
+        let block = Block {
+            header: BlockHeader {
+                version: 1,
+                height: 1,
+                previous_hash: "0".repeat(64),
+                merkle_root: merkle_root.clone(),
+                timestamp: String::new(),
+                difficulty: 0,
+                nonce: 0,
+            },
+            hash: "1".repeat(64),
+            transactions,
+        };
+
+        let mut proof = get_inclusion_proof(&block, &block.transactions[3].hash).unwrap();
+        assert_eq!(3, proof.index);
+        assert!(proof.verify(&merkle_root));
+        assert!(!proof.verify(&"0".repeat(64)));
+
+        // the leaf covers the whole transaction, signature included
+        proof.transaction.signature = "5".repeat(128);
+        assert!(!proof.verify(&merkle_root));
+        assert!(get_inclusion_proof(&block, &hash_transaction("a", "b", 1, 0, 500, "")).is_none());
+    }
 }
@@ -0,0 +1,159 @@
+// std library
+use std::error::Error;
+use std::fmt;
+
+/// Errors returned by the Mockchain library
+///
+/// # Visibility
+/// public
+///
+/// # Variants
+/// ```
+/// Io(String)                      -> a data or log file could not be read or written
+/// Json(String)                    -> data could not be converted to or from JSON
+/// Corrupt(String)                 -> a data set is missing its expected structure
+/// Schema(String)                  -> a record does not match its expected fields
+/// NotInitialised                  -> the chain data has not been initialised
+/// WalletExists(String)            -> a wallet already uses the given name
+/// WalletNotFound(String)          -> no wallet found under the given name
+/// KeyPairNotFound(String)         -> no key pair found under the given name
+/// TransactionNotFound(String)     -> no mined transaction found with the given hash
+/// BlockNotFound(u64)              -> no block found at the given height
+/// InvalidAmount(i32)              -> transaction amounts must be greater than 0
+/// InvalidFee(i32)                 -> transaction fees cannot be negative
+/// InsufficientFunds(String, i32)  -> sender cannot cover the given amount
+/// Crypto(String)                  -> a key or signature could not be decoded
+/// Config(String)                  -> the chain configuration is invalid
+/// ProfileExists(String)           -> a chain profile already uses the given name
+/// ProfileNotFound(String)         -> no chain profile found under the given name
+/// InvalidProfile(String)          -> a profile name or operation is not allowed
+/// Locked(String)                  -> another process, given by PID, holds the data lock
+/// ReadOnly(String)                -> the data was opened read-only while the given PID holds the lock
+/// PasswordRequired(String)        -> no password was given for the named key pair
+/// WrongPassword(String)           -> the password does not open the named key pair
+/// InvalidMnemonic(String)         -> a seed phrase is not valid BIP39
+/// InvalidPath(String)             -> a key derivation path could not be parsed
+/// KeyExists(String)               -> a derived key already belongs to the named wallet
+/// InvalidAddress(String)          -> an address fails its Base58Check checksum
+/// ```
+///
+/// # Derives
+/// ```
+/// Debug
+/// ```
+#[derive(Debug)]
+pub enum MockchainError {
+    Io(String),
+    Json(String),
+    Corrupt(String),
+    Schema(String),
+    NotInitialised,
+    WalletExists(String),
+    WalletNotFound(String),
+    KeyPairNotFound(String),
+    TransactionNotFound(String),
+    BlockNotFound(u64),
+    InvalidAmount(i32),
+    InvalidFee(i32),
+    InsufficientFunds(String, i32),
+    Crypto(String),
+    Config(String),
+    ProfileExists(String),
+    ProfileNotFound(String),
+    InvalidProfile(String),
+    Locked(String),
+    ReadOnly(String),
+    PasswordRequired(String),
+    WrongPassword(String),
+    InvalidMnemonic(String),
+    InvalidPath(String),
+    KeyExists(String),
+    InvalidAddress(String),
+}
+
+impl fmt::Display for MockchainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MockchainError::Io(msg) => write!(f, "I/O error: {}", msg),
+            MockchainError::Json(msg) => write!(f, "JSON error: {}", msg),
+            MockchainError::Corrupt(file) => write!(
+                f,
+                "Failed to read {}, has the data been modified or the file moved or deleted?",
+                file
+            ),
+            MockchainError::Schema(msg) => write!(f, "Unexpected data found: {}", msg),
+            MockchainError::NotInitialised => {
+                write!(f, "The blockchain data has not been initialised")
+            }
+            MockchainError::WalletExists(name) => {
+                write!(f, "Wallet with name '{}' already exists", name)
+            }
+            MockchainError::WalletNotFound(name) => {
+                write!(f, "No wallet found under name '{}'", name)
+            }
+            MockchainError::KeyPairNotFound(name) => {
+                write!(f, "No key pair found under name '{}'", name)
+            }
+            MockchainError::TransactionNotFound(hash) => {
+                write!(f, "No mined transaction found with hash '{}'", hash)
+            }
+            MockchainError::BlockNotFound(height) => {
+                write!(f, "No block found at height {}", height)
+            }
+            MockchainError::InvalidAmount(amount) => {
+                write!(
+                    f,
+                    "Choose an amount greater than 0; given value: {}",
+                    amount
+                )
+            }
+            MockchainError::InvalidFee(fee) => {
+                write!(f, "Choose a fee of 0 or more; given value: {}", fee)
+            }
+            MockchainError::InsufficientFunds(name, amount) => write!(
+                f,
+                "Not enough funds to send {} from {}'s account",
+                amount, name
+            ),
+            MockchainError::Crypto(msg) => write!(f, "Cryptography error: {}", msg),
+            MockchainError::Config(msg) => write!(f, "Invalid chain config: {}", msg),
+            MockchainError::ProfileExists(name) => {
+                write!(f, "Chain profile '{}' already exists", name)
+            }
+            MockchainError::ProfileNotFound(name) => {
+                write!(f, "No chain profile found under name '{}'", name)
+            }
+            MockchainError::InvalidProfile(msg) => write!(f, "{}", msg),
+            MockchainError::Locked(pid) => write!(
+                f,
+                "The chain data is locked by another mockchain process (PID {})",
+                pid
+            ),
+            MockchainError::ReadOnly(pid) => write!(
+                f,
+                "The chain data is read-only while another mockchain process (PID {}) holds the lock",
+                pid
+            ),
+            MockchainError::PasswordRequired(name) => write!(
+                f,
+                "The key pair for '{}' is encrypted and no password was given",
+                name
+            ),
+            MockchainError::WrongPassword(name) => {
+                write!(f, "Incorrect password for the key pair of '{}'", name)
+            }
+            MockchainError::InvalidMnemonic(msg) => write!(f, "Invalid seed phrase: {}", msg),
+            MockchainError::InvalidPath(path) => write!(f, "Invalid derivation path '{}'", path),
+            MockchainError::KeyExists(name) => {
+                write!(f, "The derived key already belongs to wallet '{}'", name)
+            }
+            MockchainError::InvalidAddress(address) => write!(
+                f,
+                "'{}' is not a valid address; check it for typos",
+                address
+            ),
+        }
+    }
+}
+
+impl Error for MockchainError {}
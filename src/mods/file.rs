@@ -1,247 +1,553 @@
 // std library
-use std::fs;
-use std::path::Path;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
 
 // 3rd party crates
 use serde::Serialize;
-use serde_json::{from_str, to_string, to_value, Value};
+use serde_json::{from_str, Map, Value};
 
 // imports
 use super::{
-    base::{Blockchain, KeyPairs, SigningData, Transactions, Wallets},
+    error::MockchainError,
     log::{Log, LogLevel},
+    store::{ChainStore, DataFile},
 };
-use crate::{
-    BLOCKCHAIN_PATH, DATA_PATH, KEYPAIRS_PATH, SIGNING_DATA_PATH, TRANSACTIONS_PATH, WALLETS_PATH,
-};
 
-/// File operations for working with JSON
+// journal of staged data sets being committed
+const JOURNAL_FILE: &str = "journal.json";
+
+// advisory lock held for as long as a writable store is open
+pub const LOCK_FILE: &str = "mockchain.lock";
+
+/// File operations for working with JSON. This is the
+/// default ChainStore backend and keeps every data set
+/// in its own JSON file under a data directory.
+///
+/// Each file is replaced by writing a temporary copy
+/// and renaming it over the original. Writes staged
+/// between begin and commit are first recorded in a
+/// journal so a commit cut short by a crash can be
+/// completed by recover.
+///
+/// A store opened with FileOps::locked holds an
+/// exclusive lock on the data directory so a second
+/// process can only open it read-only
 ///
 /// # Visibility
 /// public
 ///
 /// # Fields
-/// None
+/// ```
+/// data_path: PathBuf                       -> directory holding the JSON data files
+/// staged: Option<HashMap<DataFile, Value>> -> writes waiting for commit
+/// lock: Option<File>                       -> lock file held by this process
+/// locked_by: Option<String>                -> PID holding the lock when opened read-only
+/// ```
 ///
 /// # Derives
 /// ```
 /// serde::Serialize, Debug
 /// ```
 #[derive(Serialize, Debug)]
-pub struct FileOps {}
+pub struct FileOps {
+    data_path: PathBuf,
+    #[serde(skip)]
+    staged: Option<HashMap<DataFile, Value>>,
+    #[serde(skip)]
+    lock: Option<File>,
+    locked_by: Option<String>,
+}
 
 impl FileOps {
-    /// initialises the data files when a user first
-    /// runs the app or decides to re-initialise the
-    /// blockchain
+    /// New instance of FileOps
     ///
     /// # Visibility
     /// public
     ///
     /// # Args
     /// ```
-    /// preserve_accounts: bool -> option to preserve wallet and key data
+    /// data_path: &Path -> directory holding the JSON data files
     /// ```
     ///
     /// # Returns
-    /// Nothing
-    pub fn init(preserve_accounts: bool) {
-        if !Path::new(DATA_PATH.as_path()).exists() {
-            match fs::create_dir_all(DATA_PATH.as_path()) {
-                Ok(_) => {}
-                Err(e) => {
-                    Log::new_panic(LogLevel::ERROR, 7, None);
-                    panic!(
-                        "Error creating /.mockchain/data/ directory under $HOME: {}",
-                        e
-                    );
-                }
-            };
-        }
-
-        FileOps::init_helper(
-            &Blockchain { blockchain: [] },
-            BLOCKCHAIN_PATH.as_path(),
-            "blockchain",
-        );
-
-        FileOps::init_helper(
-            &Transactions { transactions: [] },
-            TRANSACTIONS_PATH.as_path(),
-            "transactions",
-        );
-
-        FileOps::init_helper(
-            &SigningData { signing_data: [] },
-            SIGNING_DATA_PATH.as_path(),
-            "signing",
-        );
-
-        if !preserve_accounts {
-            FileOps::init_helper(
-                &KeyPairs { keypairs: [] },
-                KEYPAIRS_PATH.as_path(),
-                "keypairs",
-            );
-
-            FileOps::init_helper(&Wallets { wallets: [] }, &WALLETS_PATH.as_path(), "wallets");
+    /// ```
+    /// FileOps
+    /// ```
+    pub fn new(data_path: &Path) -> FileOps {
+        FileOps {
+            data_path: data_path.to_path_buf(),
+            staged: None,
+            lock: None,
+            locked_by: None,
         }
     }
 
-    /// Init helper
+    /// New instance of FileOps holding the lock on its
+    /// data directory until it is dropped
     ///
     /// # Visibility
-    /// private
+    /// public
     ///
     /// # Args
     /// ```
-    /// obj: T           -> object to be serialized and written
-    /// data_file: &Path -> data file path
-    /// file_name: &str  -> data file name
+    /// data_path: &Path -> directory holding the JSON data files
     /// ```
     ///
     /// # Returns
-    /// Nothing
-    fn init_helper<T: Serialize>(obj: T, data_file: &Path, file_name: &str) {
-        let data = match to_string(&obj) {
-            Ok(val) => val,
-            Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 8, Some(vec![file_name.to_string()]));
-                panic!("Error initialising data file: {}.json: {}", &file_name, e);
-            }
-        };
-        match fs::write(data_file, data) {
+    /// ```
+    /// Result<FileOps, MockchainError> -> Locked error naming the holders PID
+    /// ```
+    pub fn locked(data_path: &Path) -> Result<FileOps, MockchainError> {
+        let mut store = FileOps::new(data_path);
+        let io_err =
+            |e: io::Error| MockchainError::Io(format!("Failed to lock {:?}: {}", data_path, e));
+        fs::create_dir_all(data_path).map_err(io_err)?;
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(data_path.join(LOCK_FILE))
+            .map_err(io_err)?;
+        match file.try_lock() {
             Ok(_) => {}
-            Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 9, Some(vec![file_name.to_string()]));
-                panic!("Failed to write {}.json: {}", &file_name, e);
+            Err(TryLockError::WouldBlock) => {
+                return Err(MockchainError::Locked(lock_holder(&mut file)))
             }
-        };
+            Err(TryLockError::Error(e)) => return Err(io_err(e)),
+        }
+        // record the PID for processes that find the directory locked
+        file.set_len(0).map_err(io_err)?;
+        file.write_all(process::id().to_string().as_bytes())
+            .map_err(io_err)?;
+        file.sync_all().map_err(io_err)?;
+        store.lock = Some(file);
+        Ok(store)
     }
 
-    /// Writes the current state of the blockchain to
-    /// file in blockchain.json
+    /// New instance of FileOps that refuses every write,
+    /// for use while another process holds the lock
     ///
     /// # Visibility
     /// public
     ///
     /// # Args
     /// ```
-    /// path -> &Path path slice
-    /// base -> &str slice of base struct name
-    /// obj  -> an object T that implements Serialize
+    /// data_path: &Path -> directory holding the JSON data files
+    /// holder: &str     -> PID of the process holding the lock
     /// ```
     ///
     /// # Returns
-    /// Nothing
+    /// ```
+    /// FileOps
+    /// ```
+    pub fn read_only(data_path: &Path, holder: &str) -> FileOps {
+        let mut store = FileOps::new(data_path);
+        store.locked_by = Some(holder.to_string());
+        store
+    }
+
+    /// Fails if this store was opened read-only
     ///
-    pub fn write<T: Serialize>(path: &Path, base: &str, obj: T) {
-        // convert the obj into a serde_json::Value
-        let value = match to_value(&obj) {
-            Ok(val) => val,
-            Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 10, Some(vec![base.to_string()]));
-                panic!("Failed to parse given object to serde_json Value: {}", e);
-            }
-        };
-        // parse data from base file
-        let mut base_data = FileOps::parse(path);
-        let data = match base_data[base].as_array_mut() {
-            Some(d) => d,
-            None => {
-                Log::new_panic(LogLevel::ERROR, 2, Some(vec![format!("{}.json", base)]));
-                panic!("Failed to read {}.json, has the data been modified or the file moved or deleted?", base);
-            }
-        };
-        data.push(value);
-        // write data back to file (full overwrite with new data appended)
-        match fs::write(path, base_data.to_string()) {
-            Ok(_) => {}
-            Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 9, Some(vec![base.to_string()]));
-                panic!("Failed to write {}.json: {}", base, e);
-            }
-        };
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    fn check_writable(&self) -> Result<(), MockchainError> {
+        match &self.locked_by {
+            Some(pid) => Err(MockchainError::ReadOnly(pid.clone())),
+            None => Ok(()),
+        }
     }
 
-    /// Write a new value to the balance field of an account
+    /// Gets the path of the JSON file for a data set
     ///
     /// # Visibility
     /// public
     ///
     /// # Args
     /// ```
-    /// address: String -> name of account to lookup
-    /// balance: i32    -> new balance to write
+    /// file: DataFile -> data set to locate
     /// ```
     ///
     /// # Returns
-    /// Nothing
-    pub fn write_balance(address: String, balance: i32) {
-        let mut base_data = FileOps::parse(WALLETS_PATH.as_path());
-        let wallets = match base_data["wallets"].as_array_mut() {
-            Some(data) => data,
-            None => {
-                Log::new_panic(LogLevel::ERROR, 2, Some(vec!["wallets.json".to_string()]));
-                panic!("Failed to read wallets.json, has the data been modified or the file moved or deleted?");
-            }
-        };
-        for wallet in wallets {
-            if wallet["address"].to_string() == address {
-                if let Ok(value) = to_value(balance) {
-                    wallet["balance"] = value;
-                    match fs::write(WALLETS_PATH.as_path(), base_data.to_string()) {
-                        Ok(_) => {}
-                        Err(e) => {
-                            Log::new_panic(LogLevel::ERROR, 9, Some(vec!["wallets".to_string()]));
-                            panic!("Failed to write wallets.json: {}", e);
-                        }
-                    };
-                    break;
-                } else {
-                    Log::new_panic(LogLevel::ERROR, 11, Some(vec![balance.to_string()]));
-                    panic!(
-                        "Failed to parse balance to serde_json Value; given value: {}",
-                        &balance
-                    );
-                };
+    /// ```
+    /// PathBuf
+    /// ```
+    pub fn path(&self, file: DataFile) -> PathBuf {
+        self.data_path.join(file.file_name())
+    }
+
+    /// Writes a full data set to file
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// data: &Value   -> base structure to write
+    /// file: DataFile -> data set being written
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    fn write_file(&self, data: &Value, file: DataFile) -> Result<(), MockchainError> {
+        match write_atomic(&self.path(file), &data.to_string()) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                Log::new_panic(LogLevel::ERROR, 9, Some(vec![file.base().to_string()]));
+                Err(MockchainError::Io(format!(
+                    "Failed to write {}: {}",
+                    file.file_name(),
+                    e
+                )))
             }
         }
     }
 
-    /// Parse a JSON string into a serde_json Value Object
+    /// Gets the path of the commit journal
     ///
     /// # Visibility
-    /// public
+    /// private
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// PathBuf
+    /// ```
+    fn journal_path(&self) -> PathBuf {
+        self.data_path.join(JOURNAL_FILE)
+    }
+
+    /// Writes every data set recorded in a journal
+    ///
+    /// # Visibility
+    /// private
     ///
     /// # Args
     /// ```
-    /// path -> &Path path slice
+    /// journal: &Map<String, Value> -> data sets keyed by file name
     /// ```
     ///
     /// # Returns
     /// ```
-    /// Value
+    /// Result<(), MockchainError>
     /// ```
-    pub fn parse(path: &Path) -> Value {
-        let json_str = match fs::read_to_string(Path::new(path)) {
+    fn apply_journal(&self, journal: &Map<String, Value>) -> Result<(), MockchainError> {
+        for file in DataFile::ALL {
+            if let Some(data) = journal.get(file.file_name()) {
+                self.write_file(data, file)?;
+            }
+        }
+        if let Err(e) = fs::remove_file(self.journal_path()) {
+            return Err(MockchainError::Io(format!(
+                "Failed to remove {}: {}",
+                JOURNAL_FILE, e
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Reads the PID written to a lock file by the
+/// process holding it
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// file: &mut File -> open lock file
+/// ```
+///
+/// # Returns
+/// ```
+/// String -> "unknown" if no PID has been written yet
+/// ```
+fn lock_holder(file: &mut File) -> String {
+    let mut pid = String::new();
+    match file.read_to_string(&mut pid) {
+        Ok(_) if !pid.trim().is_empty() => pid.trim().to_string(),
+        _ => String::from("unknown"),
+    }
+}
+
+/// Replaces the content of a file by writing a synced
+/// temporary copy next to it and renaming it over the
+/// original, so the file is never left half written
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// path: &Path    -> file to replace
+/// contents: &str -> new content of the file
+/// ```
+///
+/// # Returns
+/// ```
+/// io::Result<()>
+/// ```
+fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let tmp_path = path.with_extension("json.tmp");
+    let mut tmp = File::create(&tmp_path)?;
+    tmp.write_all(contents.as_bytes())?;
+    tmp.sync_all()?;
+    fs::rename(&tmp_path, path)
+}
+
+impl ChainStore for FileOps {
+    fn exists(&self) -> bool {
+        self.path(DataFile::Blockchain).exists()
+    }
+
+    fn init(&mut self, preserve_accounts: bool) -> Result<(), MockchainError> {
+        self.check_writable()?;
+        if !self.data_path.exists() {
+            if let Err(e) = fs::create_dir_all(&self.data_path) {
+                Log::new_panic(LogLevel::ERROR, 7, None);
+                return Err(MockchainError::Io(format!(
+                    "Error creating data directory {:?}: {}",
+                    self.data_path, e
+                )));
+            };
+        }
+
+        let mut files = vec![
+            DataFile::Blockchain,
+            DataFile::Transactions,
+            DataFile::SigningData,
+        ];
+        if !preserve_accounts {
+            files.push(DataFile::KeyPairs);
+            files.push(DataFile::Wallets);
+        }
+        for file in files {
+            self.overwrite(file, file.empty()?)?;
+        }
+        Ok(())
+    }
+
+    fn parse(&self, file: DataFile) -> Result<Value, MockchainError> {
+        if let Some(data) = self.staged.as_ref().and_then(|staged| staged.get(&file)) {
+            return Ok(data.clone());
+        }
+        let path = self.path(file);
+        let json_str = match fs::read_to_string(&path) {
             Ok(content) => content,
             Err(e) => {
                 Log::new_panic(LogLevel::ERROR, 12, Some(vec![format!("{:?}", path)]));
-                panic!(
-                    "Error parsing data file content at {}: {}",
-                    format!("{:?}", path),
-                    e
-                );
+                return Err(MockchainError::Io(format!(
+                    "Error parsing data file content at {:?}: {}",
+                    path, e
+                )));
             }
         };
-        let value = match from_str(&json_str) {
-            Ok(val) => val,
+        match from_str(&json_str) {
+            Ok(val) => Ok(val),
             Err(e) => {
                 Log::new_panic(LogLevel::ERROR, 13, Some(vec![json_str.clone()]));
-                panic!("Poorly formatted JSON found: {}", e);
+                Err(MockchainError::Json(format!(
+                    "Poorly formatted JSON found in {}: {}",
+                    file.file_name(),
+                    e
+                )))
             }
+        }
+    }
+
+    fn overwrite(&mut self, file: DataFile, data: Value) -> Result<(), MockchainError> {
+        self.check_writable()?;
+        match self.staged.as_mut() {
+            Some(staged) => {
+                staged.insert(file, data);
+                Ok(())
+            }
+            None => self.write_file(&data, file),
+        }
+    }
+
+    fn begin(&mut self) -> Result<(), MockchainError> {
+        self.check_writable()?;
+        if self.staged.is_some() {
+            return Err(MockchainError::Io(
+                "A write is already in progress".to_string(),
+            ));
+        }
+        self.staged = Some(HashMap::new());
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), MockchainError> {
+        let staged = match self.staged.take() {
+            Some(staged) if !staged.is_empty() => staged,
+            _ => return Ok(()),
         };
-        value
+        let journal: Map<String, Value> = staged
+            .into_iter()
+            .map(|(file, data)| (file.file_name().to_string(), data))
+            .collect();
+        // once the journal is in place the commit will be completed
+        let contents = Value::Object(journal.clone()).to_string();
+        if let Err(e) = write_atomic(&self.journal_path(), &contents) {
+            return Err(MockchainError::Io(format!(
+                "Failed to write {}: {}",
+                JOURNAL_FILE, e
+            )));
+        }
+        self.apply_journal(&journal)
+    }
+
+    fn rollback(&mut self) {
+        self.staged = None;
+    }
+
+    fn recover(&mut self) -> Result<bool, MockchainError> {
+        // a journal left while locked belongs to the commit of the holder
+        if !self.data_path.exists() || self.locked_by.is_some() {
+            return Ok(false);
+        }
+        // temporary copies were never renamed so their writes never happened
+        if let Ok(entries) = fs::read_dir(&self.data_path) {
+            for entry in entries.flatten() {
+                if entry.file_name().to_string_lossy().ends_with(".tmp") {
+                    let _ = fs::remove_file(entry.path());
+                }
+            }
+        }
+        let journal_path = self.journal_path();
+        if !journal_path.exists() {
+            return Ok(false);
+        }
+        let journal = fs::read_to_string(&journal_path)
+            .ok()
+            .and_then(|content| from_str::<Map<String, Value>>(&content).ok());
+        match journal {
+            Some(journal) => {
+                self.apply_journal(&journal)?;
+                Log::new(LogLevel::WARNING, 45, Some(vec![journal.len().to_string()]));
+                Ok(true)
+            }
+            None => {
+                let _ = fs::remove_file(&journal_path);
+                Log::new(LogLevel::WARNING, 46, None);
+                Ok(false)
+            }
+        }
+    }
+
+    fn locked_by(&self) -> Option<String> {
+        self.locked_by.clone()
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_file {
+    use super::*;
+
+    use serde_json::json;
+    use std::env;
+    use std::process;
+
+    fn temp_store(name: &str) -> FileOps {
+        let path = env::temp_dir().join(format!("mockchain-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&path);
+        let mut store = FileOps::new(&path);
+        store.init(false).unwrap();
+        store
+    }
+
+    #[test]
+    fn test_staged_writes_commit_or_roll_back_together() {
+        let mut store = temp_store("staged");
+        let blocks = json!({ "blockchain": [{ "hash": "00ab" }] });
+        let pending = json!({ "transactions": [] });
+
+        store.begin().unwrap();
+        store
+            .overwrite(DataFile::Blockchain, blocks.clone())
+            .unwrap();
+        assert_eq!(blocks, store.parse(DataFile::Blockchain).unwrap());
+        store.rollback();
+        assert_eq!(
+            DataFile::Blockchain.empty().unwrap(),
+            store.parse(DataFile::Blockchain).unwrap()
+        );
+
+        store.begin().unwrap();
+        store
+            .overwrite(DataFile::Blockchain, blocks.clone())
+            .unwrap();
+        store.overwrite(DataFile::Transactions, pending).unwrap();
+        store.commit().unwrap();
+        assert_eq!(blocks, store.parse(DataFile::Blockchain).unwrap());
+        assert!(!store.journal_path().exists());
+        fs::remove_dir_all(&store.data_path).unwrap();
+    }
+
+    #[test]
+    fn test_second_store_is_locked_out() {
+        let path = temp_store("lock").data_path;
+        let mut store = FileOps::locked(&path).unwrap();
+        store.init(false).unwrap();
+
+        let pid = process::id().to_string();
+        match FileOps::locked(&path) {
+            Err(MockchainError::Locked(holder)) => assert_eq!(pid, holder),
+            other => panic!("expected Locked, got {:?}", other),
+        }
+        let mut reader = FileOps::read_only(&path, &pid);
+        assert!(reader.parse(DataFile::Blockchain).is_ok());
+        assert!(matches!(
+            reader.clear(DataFile::Blockchain),
+            Err(MockchainError::ReadOnly(_))
+        ));
+
+        // dropping the store releases the lock
+        drop(store);
+        assert!(FileOps::locked(&path).is_ok());
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_recover_completes_interrupted_commit() {
+        let mut store = temp_store("recover");
+        let blocks = json!({ "blockchain": [{ "hash": "00ab" }] });
+
+        // crash after the journal was written but before blockchain.json
+        let journal = json!({ "blockchain.json": blocks });
+        fs::write(store.journal_path(), journal.to_string()).unwrap();
+        fs::write(
+            store.path(DataFile::Wallets).with_extension("json.tmp"),
+            "{",
+        )
+        .unwrap();
+
+        assert!(store.recover().unwrap());
+        assert_eq!(blocks, store.parse(DataFile::Blockchain).unwrap());
+        assert!(!store.journal_path().exists());
+        assert_eq!(5, fs::read_dir(&store.data_path).unwrap().count());
+
+        // a journal cut short is discarded and the data left alone
+        fs::write(store.journal_path(), "{\"blockchain.json\": {").unwrap();
+        assert!(!store.recover().unwrap());
+        assert_eq!(blocks, store.parse(DataFile::Blockchain).unwrap());
+        assert!(!store.journal_path().exists());
+        fs::remove_dir_all(&store.data_path).unwrap();
     }
 }
@@ -0,0 +1,408 @@
+/*
+    Genesis block described by genesis.json
+*/
+
+// std library
+use std::fs;
+use std::path::Path;
+
+// 3rd party crates
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::from_str;
+
+// imports
+use super::{
+    address::validate_address,
+    block::{Block, BlockHeader, BLOCK_VERSION},
+    config::MAX_DIFFICULTY,
+    crypto::{get_merkle_root, hash_block, hash_chain_id, hash_transaction, meets_difficulty},
+    error::MockchainError,
+    transaction::{Transaction, GENESIS},
+};
+
+// id of chains created without a genesis spec
+pub const DEFAULT_CHAIN_ID: &str = "mockchain";
+// timestamp of the genesis block of chains created without a genesis spec
+pub const DEFAULT_GENESIS_TIMESTAMP: &str = "2024-01-01T00:00:00+00:00";
+
+/// Tokens credited to an address by the genesis block
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// address: String -> address receiving the tokens
+/// amount: i32     -> tokens credited
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Allocation {
+    pub address: String,
+    pub amount: i32,
+}
+
+/// Spec the genesis block of a chain is built from.
+/// The genesis block is fully determined by the spec,
+/// so the spec can also pin its hash. Every field is
+/// optional in genesis.json and falls back to its
+/// default
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// chain_id: String               -> id of the chain, hashed into the previous hash of genesis
+/// timestamp: String              -> RFC3339 timestamp of the genesis block
+/// difficulty: u32                -> leading zeros the genesis hash must start with
+/// allocations: Vec<Allocation>   -> tokens credited to addresses before any block is mined
+/// hash: Option<String>           -> hash the genesis block must have, unchecked when None
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct GenesisSpec {
+    pub chain_id: String,
+    pub timestamp: String,
+    pub difficulty: u32,
+    pub allocations: Vec<Allocation>,
+    pub hash: Option<String>,
+}
+
+impl Default for GenesisSpec {
+    fn default() -> GenesisSpec {
+        GenesisSpec {
+            chain_id: String::from(DEFAULT_CHAIN_ID),
+            timestamp: String::from(DEFAULT_GENESIS_TIMESTAMP),
+            difficulty: 0,
+            allocations: Vec::new(),
+            hash: None,
+        }
+    }
+}
+
+impl GenesisSpec {
+    /// Loads a genesis spec from a JSON file. The default
+    /// spec is used when the file does not exist
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// path: &Path -> path to genesis.json
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<GenesisSpec, MockchainError>
+    /// ```
+    pub fn load(path: &Path) -> Result<GenesisSpec, MockchainError> {
+        if !path.exists() {
+            return Ok(GenesisSpec::default());
+        }
+        let json_str = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                return Err(MockchainError::Io(format!(
+                    "Error reading genesis file at {:?}: {}",
+                    path, e
+                )))
+            }
+        };
+        let spec: GenesisSpec = match from_str(&json_str) {
+            Ok(spec) => spec,
+            Err(e) => {
+                return Err(MockchainError::Config(format!(
+                    "Poorly formatted JSON found in {:?}: {}",
+                    path, e
+                )))
+            }
+        };
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Checks the spec describes a genesis block that can
+    /// be built. A pinned hash is only checked to be a
+    /// hash here, the genesis block is not mined to
+    /// compare against it
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    pub fn validate(&self) -> Result<(), MockchainError> {
+        if self.chain_id.trim().is_empty() {
+            return Err(MockchainError::Config(
+                "genesis chain_id cannot be empty".to_string(),
+            ));
+        }
+        if DateTime::parse_from_rfc3339(&self.timestamp).is_err() {
+            return Err(MockchainError::Config(format!(
+                "genesis timestamp must be RFC3339; given value: {}",
+                self.timestamp
+            )));
+        }
+        if self.difficulty > MAX_DIFFICULTY {
+            return Err(MockchainError::Config(format!(
+                "genesis difficulty must be at most {}; given value: {}",
+                MAX_DIFFICULTY, self.difficulty
+            )));
+        }
+        let mut total: i64 = 0;
+        for allocation in self.allocations.iter() {
+            validate_address(&allocation.address)?;
+            if allocation.amount <= 0 {
+                return Err(MockchainError::InvalidAmount(allocation.amount));
+            }
+            total += allocation.amount as i64;
+        }
+        if total > i32::MAX as i64 {
+            return Err(MockchainError::Config(format!(
+                "genesis allocations add up to {} tokens; at most {} can be allocated",
+                total,
+                i32::MAX
+            )));
+        }
+        if let Some(pinned) = &self.hash {
+            if pinned.len() != 64 || !pinned.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(MockchainError::Config(format!(
+                    "genesis hash must be 64 hex characters; given value: {}",
+                    pinned
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Builds the allocation transactions of the genesis
+    /// block. Allocations are not signed and, like a
+    /// coinbase, are sent from a sentinel address, with
+    /// their position in the spec as the nonce
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Vec<Transaction>
+    /// ```
+    pub fn transactions(&self) -> Vec<Transaction> {
+        self.allocations
+            .iter()
+            .enumerate()
+            .map(|(i, allocation)| Transaction {
+                hash: hash_transaction(
+                    GENESIS,
+                    &allocation.address,
+                    allocation.amount,
+                    0,
+                    i as u64,
+                    &self.timestamp,
+                ),
+                from_address: String::from(GENESIS),
+                to_address: allocation.address.clone(),
+                public_key: String::new(),
+                amount: allocation.amount,
+                fee: 0,
+                nonce: i as u64,
+                timestamp: self.timestamp.clone(),
+                signature: String::new(),
+            })
+            .collect()
+    }
+
+    /// Builds the genesis block, mining it at the spec
+    /// difficulty from a nonce of 0 so the same spec
+    /// always gives the same block. Mining can take a
+    /// while at high difficulties, so the block is only
+    /// built when the chain is created
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Block, MockchainError> -> Config if the block does not have the pinned hash
+    /// ```
+    pub fn block(&self) -> Result<Block, MockchainError> {
+        let transactions = self.transactions();
+        let mut header = BlockHeader {
+            version: BLOCK_VERSION,
+            height: 0,
+            previous_hash: hash_chain_id(&self.chain_id),
+            merkle_root: get_merkle_root(&transactions),
+            timestamp: self.timestamp.clone(),
+            difficulty: self.difficulty,
+            nonce: 0,
+        };
+        let hash = header.mine();
+        if let Some(pinned) = self.hash.as_ref().filter(|pinned| **pinned != hash) {
+            return Err(MockchainError::Config(format!(
+                "genesis spec pins the hash {} but gives a genesis block with hash {}",
+                pinned, hash
+            )));
+        }
+        Ok(Block {
+            hash,
+            header,
+            transactions,
+        })
+    }
+
+    /// Checks a Block is the genesis block built from the
+    /// spec, with the transactions its header commits to.
+    /// The stored header is checked against the spec and
+    /// its proof of work checked, so the block is not
+    /// mined again
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// block: &Block -> first Block of the chain
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    pub fn matches(&self, block: &Block) -> bool {
+        let header = &block.header;
+        header.version == BLOCK_VERSION
+            && header.height == 0
+            && header.previous_hash == hash_chain_id(&self.chain_id)
+            && header.merkle_root == get_merkle_root(&self.transactions())
+            && header.timestamp == self.timestamp
+            && header.difficulty == self.difficulty
+            && get_merkle_root(&block.transactions) == header.merkle_root
+            && hash_block(header) == block.hash
+            && meets_difficulty(&block.hash, header.difficulty)
+            && self
+                .hash
+                .as_ref()
+                .is_none_or(|pinned| *pinned == block.hash)
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_genesis {
+    use super::*;
+
+    const ADDRESS: &str = "mzzQBDJPHSubZsZ2PC8jaVvAL6qLBqTHLd";
+
+    #[test]
+    fn test_genesis_block() {
+        let spec = GenesisSpec::default();
+        let genesis = spec.block().unwrap();
+        assert_eq!(
+            "eec5570888a13bf8632a02871e649bf17573e5eb2084d540b4850d7003db8b31",
+            genesis.hash
+        );
+        assert!(spec.matches(&genesis));
+
+        // another chain id gives another genesis block
+        let other = GenesisSpec {
+            chain_id: String::from("testnet"),
+            ..GenesisSpec::default()
+        };
+        assert_ne!(genesis.hash, other.block().unwrap().hash);
+        assert!(!other.matches(&genesis));
+
+        // the stored hash must be the hash of the header and meet its difficulty
+        let mut renonced = genesis.clone();
+        renonced.header.nonce += 1;
+        assert!(!spec.matches(&renonced));
+        let mut harder = genesis.clone();
+        harder.header.difficulty = 1;
+        harder.hash = harder.header.hash();
+        assert!(!spec.matches(&harder));
+
+        // allocations are committed to through the merkle root
+        let funded = GenesisSpec {
+            allocations: vec![Allocation {
+                address: String::from(ADDRESS),
+                amount: 500,
+            }],
+            difficulty: 1,
+            ..GenesisSpec::default()
+        };
+        let mut block = funded.block().unwrap();
+        assert!(block.hash.starts_with('0'));
+        assert!(block.transactions[0].is_allocation());
+        assert!(funded.matches(&block));
+        block.transactions[0].amount = 5000;
+        assert!(!funded.matches(&block));
+
+        // a pinned hash must be the hash of the block
+        let pinned = GenesisSpec {
+            hash: Some(genesis.hash.clone()),
+            ..GenesisSpec::default()
+        };
+        assert!(pinned.matches(&genesis));
+        let wrong_pin = GenesisSpec {
+            hash: Some("0".repeat(64)),
+            ..GenesisSpec::default()
+        };
+        assert!(!wrong_pin.matches(&genesis));
+        assert!(matches!(wrong_pin.block(), Err(MockchainError::Config(_))));
+    }
+
+    #[test]
+    fn test_validate_genesis_spec() {
+        assert!(GenesisSpec::default().validate().is_ok());
+
+        let pinned = GenesisSpec {
+            hash: Some("0".repeat(64)),
+            ..GenesisSpec::default()
+        };
+        assert!(pinned.validate().is_ok());
+        let bad_pin = GenesisSpec {
+            hash: Some(String::from("genesis")),
+            ..GenesisSpec::default()
+        };
+        assert!(matches!(bad_pin.validate(), Err(MockchainError::Config(_))));
+
+        let bad_address = GenesisSpec {
+            allocations: vec![Allocation {
+                address: String::from("alice"),
+                amount: 10,
+            }],
+            ..GenesisSpec::default()
+        };
+        assert!(matches!(
+            bad_address.validate(),
+            Err(MockchainError::InvalidAddress(_))
+        ));
+        let bad_timestamp = GenesisSpec {
+            timestamp: String::from("yesterday"),
+            ..GenesisSpec::default()
+        };
+        assert!(bad_timestamp.validate().is_err());
+    }
+}
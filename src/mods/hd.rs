@@ -0,0 +1,352 @@
+/*
+    Seed phrases and hierarchical deterministic key derivation
+*/
+
+// 3rd party crates
+use bip39::Mnemonic;
+use hmac::{Hmac, Mac};
+use p256::{
+    elliptic_curve::{sec1::ToEncodedPoint, PrimeField},
+    FieldBytes, Scalar, SecretKey,
+};
+use rand_core::{OsRng, RngCore};
+use sha2::Sha512;
+
+// imports
+use super::error::MockchainError;
+
+// accounts are derived at <ACCOUNT_PATH>/<index>, 1' marks a test network
+pub const ACCOUNT_PATH: &str = "m/44'/1'/0'/0";
+
+// bytes of entropy behind a 12 word seed phrase
+const ENTROPY_LEN: usize = 16;
+
+// indexes from 2^31 up are hardened
+const HARDENED: u32 = 0x8000_0000;
+
+// HMAC key for master keys on the P-256 curve, as in SLIP-10
+const CURVE_SEED: &[u8] = b"Nist256p1 seed";
+
+/// Generates a new 12 word BIP39 seed phrase
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// None
+///
+/// # Returns
+/// ```
+/// Result<String, MockchainError>
+/// ```
+pub fn generate_mnemonic() -> Result<String, MockchainError> {
+    let mut entropy = [0u8; ENTROPY_LEN];
+    OsRng.fill_bytes(&mut entropy);
+    match Mnemonic::from_entropy(&entropy) {
+        Ok(mnemonic) => Ok(mnemonic.to_string()),
+        Err(e) => Err(MockchainError::InvalidMnemonic(e.to_string())),
+    }
+}
+
+/// Checks a BIP39 seed phrase and turns it into the
+/// seed keys are derived from
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// phrase: &str     -> seed phrase, any case or spacing
+/// passphrase: &str -> optional BIP39 passphrase, "" for none
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<[u8; 64], MockchainError>
+/// ```
+pub fn mnemonic_to_seed(phrase: &str, passphrase: &str) -> Result<[u8; 64], MockchainError> {
+    let phrase = phrase
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase();
+    match Mnemonic::parse_normalized(&phrase) {
+        Ok(mnemonic) => Ok(mnemonic.to_seed(passphrase)),
+        Err(e) => Err(MockchainError::InvalidMnemonic(e.to_string())),
+    }
+}
+
+/// Gets the derivation path of an account
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// index: u32 -> index of the account
+/// ```
+///
+/// # Returns
+/// ```
+/// String
+/// ```
+pub fn account_path(index: u32) -> String {
+    format!("{}/{}", ACCOUNT_PATH, index)
+}
+
+/// Parses a derivation path such as m/44'/1'/0'/0/3
+/// into child indexes. Hardened indexes are marked
+/// with ' or h
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// path: &str -> derivation path
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<Vec<u32>, MockchainError>
+/// ```
+pub fn parse_path(path: &str) -> Result<Vec<u32>, MockchainError> {
+    let mut parts = path.split('/');
+    if parts.next() != Some("m") {
+        return Err(MockchainError::InvalidPath(path.to_string()));
+    }
+    parts
+        .map(|part| {
+            let (digits, offset) = match part.strip_suffix('\'').or(part.strip_suffix('h')) {
+                Some(digits) => (digits, HARDENED),
+                None => (part, 0),
+            };
+            match digits.parse::<u32>() {
+                Ok(index) if index < HARDENED => Ok(index + offset),
+                _ => Err(MockchainError::InvalidPath(path.to_string())),
+            }
+        })
+        .collect()
+}
+
+/// Private key and chain code at one node of a key
+/// tree, derived as described by SLIP-10 for the
+/// NIST P-256 curve
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// secret: SecretKey     -> private key at this node
+/// chain_code: [u8; 32]  -> entropy mixed into child keys
+/// ```
+///
+/// # Derives
+/// ```
+/// Clone
+/// ```
+#[derive(Clone)]
+pub struct ExtendedKey {
+    pub secret: SecretKey,
+    pub chain_code: [u8; 32],
+}
+
+impl ExtendedKey {
+    /// Derives the master key of a seed
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// seed: &[u8] -> seed from mnemonic_to_seed
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<ExtendedKey, MockchainError>
+    /// ```
+    pub fn master(seed: &[u8]) -> Result<ExtendedKey, MockchainError> {
+        let mut digest = hmac_sha512(CURVE_SEED, &[seed])?;
+        loop {
+            let (key, chain_code) = digest.split_at(32);
+            // keys outside the curve order are hashed again
+            if let Ok(secret) = SecretKey::from_slice(key) {
+                return Ok(ExtendedKey {
+                    secret,
+                    chain_code: to_chain_code(chain_code),
+                });
+            }
+            digest = hmac_sha512(CURVE_SEED, &[&digest])?;
+        }
+    }
+
+    /// Derives a child key
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// index: u32 -> child index, hardened from 2^31 up
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<ExtendedKey, MockchainError>
+    /// ```
+    pub fn child(&self, index: u32) -> Result<ExtendedKey, MockchainError> {
+        let index_bytes = index.to_be_bytes();
+        let parent = self.secret.to_bytes();
+        let mut digest = if index >= HARDENED {
+            hmac_sha512(&self.chain_code, &[&[0u8], &parent, &index_bytes])?
+        } else {
+            let point = self.secret.public_key().to_encoded_point(true);
+            hmac_sha512(&self.chain_code, &[point.as_bytes(), &index_bytes])?
+        };
+        let parent_scalar: Scalar = *self.secret.to_nonzero_scalar();
+        loop {
+            let (tweak, chain_code) = digest.split_at(32);
+            let tweak = Option::<Scalar>::from(Scalar::from_repr(*FieldBytes::from_slice(tweak)));
+            if let Some(tweak) = tweak {
+                let child = tweak + parent_scalar;
+                if let Ok(secret) = SecretKey::from_bytes(&child.to_bytes()) {
+                    return Ok(ExtendedKey {
+                        secret,
+                        chain_code: to_chain_code(chain_code),
+                    });
+                }
+            }
+            // an invalid key moves on to the next candidate
+            digest = hmac_sha512(&self.chain_code, &[&[1u8], chain_code, &index_bytes])?;
+        }
+    }
+
+    /// Derives the key at a path below the master key
+    /// of a seed
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// seed: &[u8] -> seed from mnemonic_to_seed
+    /// path: &str  -> derivation path e.g. m/44'/1'/0'/0/0
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<ExtendedKey, MockchainError>
+    /// ```
+    pub fn derive(seed: &[u8], path: &str) -> Result<ExtendedKey, MockchainError> {
+        let mut key = ExtendedKey::master(seed)?;
+        for index in parse_path(path)? {
+            key = key.child(index)?;
+        }
+        Ok(key)
+    }
+}
+
+/// Computes HMAC-SHA512 over a series of byte slices
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// key: &[u8]      -> HMAC key
+/// data: &[&[u8]]  -> slices hashed in order
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<Vec<u8>, MockchainError>
+/// ```
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> Result<Vec<u8>, MockchainError> {
+    let mut mac = match Hmac::<Sha512>::new_from_slice(key) {
+        Ok(mac) => mac,
+        Err(e) => return Err(MockchainError::Crypto(e.to_string())),
+    };
+    for part in data {
+        mac.update(part);
+    }
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+/// Copies the right half of a digest into a chain code
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// bytes: &[u8] -> 32 bytes
+/// ```
+///
+/// # Returns
+/// ```
+/// [u8; 32]
+/// ```
+fn to_chain_code(bytes: &[u8]) -> [u8; 32] {
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(bytes);
+    chain_code
+}
+
+// Testing
+#[cfg(test)]
+mod test_hd {
+    use super::*;
+
+    use hex::{decode, encode};
+
+    #[test]
+    fn test_slip10_vectors() {
+        // SLIP-10 test vector 1 for nist256p1
+        let seed = decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let cases = [
+            (
+                "m",
+                "beeb672fe4621673f722f38529c07392fecaa61015c80c34f29ce8b41b3cb6ea",
+                "612091aaa12e22dd2abef664f8a01a82cae99ad7441b7ef8110424915c268bc2",
+            ),
+            (
+                "m/0'",
+                "3460cea53e6a6bb5fb391eeef3237ffd8724bf0a40e94943c98b83825342ee11",
+                "6939694369114c67917a182c59ddb8cafc3004e63ca5d3b84403ba8613debc0c",
+            ),
+            (
+                "m/0'/1",
+                "4187afff1aafa8445010097fb99d23aee9f599450c7bd140b6826ac22ba21d0c",
+                "284e9d38d07d21e4e281b645089a94f4cf5a5a81369acf151a1c3a57f18b2129",
+            ),
+        ];
+        for (path, chain_code, private_key) in cases {
+            let key = ExtendedKey::derive(&seed, path).unwrap();
+            assert_eq!(chain_code, encode(key.chain_code));
+            assert_eq!(private_key, encode(key.secret.to_bytes()));
+        }
+    }
+
+    #[test]
+    fn test_mnemonic_restores_the_same_keys() {
+        let phrase = generate_mnemonic().unwrap();
+        assert_eq!(12, phrase.split_whitespace().count());
+        let seed = mnemonic_to_seed(&phrase, "").unwrap();
+        // case and spacing do not change the seed
+        let retyped = format!("  {}  ", phrase.to_uppercase().replace(' ', "   "));
+        assert_eq!(seed, mnemonic_to_seed(&retyped, "").unwrap());
+        assert_ne!(seed, mnemonic_to_seed(&phrase, "extra").unwrap());
+
+        let first = ExtendedKey::derive(&seed, &account_path(0)).unwrap();
+        let again = ExtendedKey::derive(&seed, &account_path(0)).unwrap();
+        let second = ExtendedKey::derive(&seed, &account_path(1)).unwrap();
+        assert_eq!(first.secret.to_bytes(), again.secret.to_bytes());
+        assert_ne!(first.secret.to_bytes(), second.secret.to_bytes());
+
+        assert!(mnemonic_to_seed("not a real seed phrase", "").is_err());
+        assert!(parse_path("44'/0").is_err());
+        assert!(parse_path("m/2147483648").is_err());
+    }
+}
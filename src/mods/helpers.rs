@@ -1,17 +1,23 @@
 // 3rd party crates
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 
 // imports
 use super::{
-    block::Block,
-    crypto::{get_merkle_root, hash_block, hash_transaction, KeyPair},
-    file::FileOps,
+    address::{to_address, validate_address},
+    block::{Block, BlockHeader, BLOCK_VERSION},
+    config::ChainConfig,
+    crypto::{get_merkle_root, hash_transaction, meets_difficulty, KeyPair},
+    error::MockchainError,
+    keystore::Keystore,
     log::{Log, LogLevel},
+    mempool::{Mempool, Sequence},
     signing_data::Signing,
+    state::ChainState,
+    store::{to_record, to_records, ChainStore, DataFile},
     transaction::Transaction,
+    verification::{FailureKind, VerificationReport},
     wallet::Wallet,
 };
-use crate::{BLOCKCHAIN_PATH, KEYPAIRS_PATH, SIGNING_DATA_PATH, TRANSACTIONS_PATH, WALLETS_PATH};
 
 /// Gets an RFC3339 timestamp
 ///
@@ -26,9 +32,7 @@ use crate::{BLOCKCHAIN_PATH, KEYPAIRS_PATH, SIGNING_DATA_PATH, TRANSACTIONS_PATH
 /// String
 /// ```
 pub fn get_timestamp() -> String {
-    let now = Utc::now();
-    let timestamp = now.to_rfc3339();
-    timestamp
+    Utc::now().to_rfc3339()
 }
 
 /// Creates a wallet
@@ -38,22 +42,57 @@ pub fn get_timestamp() -> String {
 ///
 /// # Args
 /// ```
-/// name: String -> name of the account
+/// store: &mut dyn ChainStore -> storage backend holding the chain
+/// name: &str                 -> name of the account
+/// keystore: &mut Keystore    -> keystore sealing the new private key
 /// ```
 /// # Returns
-/// Nothing
-pub fn create_wallet(name: String) {
-    let key_pair = KeyPair::generate(name.clone());
+/// ```
+/// Result<Wallet, MockchainError>
+/// ```
+pub fn create_wallet(
+    store: &mut dyn ChainStore,
+    name: &str,
+    keystore: &mut Keystore,
+) -> Result<Wallet, MockchainError> {
+    add_wallet(store, KeyPair::generate(name.to_string()), keystore)
+}
+
+/// Stores a key pair and a wallet for its address
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// store: &mut dyn ChainStore -> storage backend holding the chain
+/// key_pair: KeyPair          -> new key pair, named after the account
+/// keystore: &mut Keystore    -> keystore sealing the private key
+/// ```
+/// # Returns
+/// ```
+/// Result<Wallet, MockchainError>
+/// ```
+pub fn add_wallet(
+    store: &mut dyn ChainStore,
+    key_pair: KeyPair,
+    keystore: &mut Keystore,
+) -> Result<Wallet, MockchainError> {
+    let name = key_pair.name.clone();
+    let key_pair = keystore.seal(key_pair)?;
     Log::new(LogLevel::INFO, 6, Some(vec![name.clone()]));
-    let address = key_pair.public_key.clone();
+    let address = to_address(&key_pair.public_key)?;
+    // the address may already hold tokens, e.g. from a genesis allocation
+    let balance = ChainState::from_store(store)?.balance(&address);
     let wallet = Wallet {
         name: name.clone(),
         address,
-        balance: 0,
+        balance,
     };
     Log::new(LogLevel::INFO, 7, Some(vec![name]));
-    FileOps::write(KEYPAIRS_PATH.as_path(), "keypairs", key_pair);
-    FileOps::write(WALLETS_PATH.as_path(), "wallets", wallet);
+    store.write(DataFile::KeyPairs, to_record(key_pair, DataFile::KeyPairs)?)?;
+    store.write(DataFile::Wallets, to_record(&wallet, DataFile::Wallets)?)?;
+    Ok(wallet)
 }
 
 /// Creates a Transaction
@@ -63,81 +102,90 @@ pub fn create_wallet(name: String) {
 ///
 /// # Args
 /// ```
-/// from: String -> name of sender
-/// to: String   -> name of recipient
-/// amount: i32  -> amount
+/// store: &mut dyn ChainStore -> storage backend holding the chain
+/// from: &str                 -> name of sender
+/// to: &str                   -> name or address of recipient
+/// amount: i32                -> amount
+/// fee: i32                   -> fee paid to the miner
+/// keystore: &mut Keystore    -> keystore unlocking the senders private key
 /// ```
 ///
 /// # Returns
-/// Nothing
-pub fn create_transaction(from: String, to: String, amount: i32) {
+/// ```
+/// Result<Transaction, MockchainError>
+/// ```
+pub fn create_transaction(
+    store: &mut dyn ChainStore,
+    from: &str,
+    to: &str,
+    amount: i32,
+    fee: i32,
+    keystore: &mut Keystore,
+) -> Result<Transaction, MockchainError> {
     Log::new(LogLevel::INFO, 19, None);
     // get wallet public keys
-    let from_address = match Wallet::get_wallet_address(&from) {
-        Some(key) => key.replace("\"", ""),
-        None => return,
-    };
-    let to_address = match Wallet::get_wallet_address(&to) {
-        Some(key) => key.replace("\"", ""),
-        None => return,
+    let from_address = match Wallet::get_wallet_address(store, from)? {
+        Some(key) => key,
+        None => return Err(MockchainError::WalletNotFound(from.to_string())),
     };
-    Log::new(LogLevel::INFO, 20, Some(vec![from.clone(), to.clone()]));
+    let to_address = Wallet::resolve_address(store, to)?;
+    // catch a damaged address before anything is signed
+    validate_address(&from_address)?;
+    validate_address(&to_address)?;
+    let public_key = KeyPair::get_key(store, from, "public")?;
+    Log::new(
+        LogLevel::INFO,
+        20,
+        Some(vec![from.to_string(), to.to_string()]),
+    );
+
+    // get the senders next nonce
+    let nonce = Transaction::next_nonce(store, &from_address)?;
+    let timestamp = get_timestamp();
 
     // get transaction hash
-    let hash = hash_transaction(&from_address, &to_address, &amount.to_string());
+    let hash = hash_transaction(&from_address, &to_address, amount, fee, nonce, &timestamp);
     Log::new(LogLevel::INFO, 21, None);
 
-    // get senders private key
-    let mut base_data = FileOps::parse(KEYPAIRS_PATH.as_path());
-    let key_data = match base_data["keypairs"].as_array_mut() {
-        Some(arr) => arr,
-        None => {
-            Log::new_panic(LogLevel::ERROR, 2, Some(vec!["keypairs.json".to_string()]));
-            panic!("Failed to read keypairs.json, has the data been modified or the file moved or deleted?");
-        }
-    };
+    // get senders private key, a failure to unlock it is logged by the keystore
+    let private_key = keystore.private_key(store, from)?;
+    Log::new(LogLevel::INFO, 22, Some(vec![from.to_string()]));
 
-    let mut private_key = String::from("");
-    for key_pair in key_data {
-        if key_pair["name"] == from {
-            let key_value = match key_pair["private_key"].as_str() {
-                Some(val) => val,
-                None => {
-                    Log::new_panic(LogLevel::ERROR, 15, None);
-                    panic!(
-                        "Failed to parse private key from json_serde Value to &str while creating transaction"
-                    );
-                }
-            };
-            private_key.push_str(key_value);
-        }
-    }
-    Log::new(LogLevel::INFO, 22, Some(vec![from.clone()]));
-
-    // get the transaction signature and the signing key
-    let (signature, signing_key) = KeyPair::sign(&hash, private_key);
-    Log::new(LogLevel::INFO, 23, Some(vec![from.clone()]));
+    // get the transaction signature
+    let signature = KeyPair::sign(&hash, &private_key)?;
+    Log::new(LogLevel::INFO, 23, Some(vec![from.to_string()]));
 
     let signing_data = Signing {
-        name: from,
+        name: from.to_string(),
         hash: hash.clone(),
-        signing_key,
+        public_key: public_key.clone(),
         signature: signature.clone(),
     };
 
     let transaction = Transaction {
-        hash: hash.clone(),
+        hash,
         from_address,
         to_address,
+        public_key,
         amount,
-        signature: signature.clone(),
+        fee,
+        nonce,
+        timestamp,
+        signature,
     };
 
     // write objects to file
-    FileOps::write(TRANSACTIONS_PATH.as_path(), "transactions", transaction);
+    store.write(
+        DataFile::Transactions,
+        to_record(&transaction, DataFile::Transactions)?,
+    )?;
     Log::new(LogLevel::INFO, 25, None);
-    FileOps::write(SIGNING_DATA_PATH.as_path(), "signing_data", signing_data);
+    store.write(
+        DataFile::SigningData,
+        to_record(signing_data, DataFile::SigningData)?,
+    )?;
     Log::new(LogLevel::INFO, 24, None);
+    Ok(transaction)
 }
 
 /// Mine the next block in the chain
@@ -147,98 +195,80 @@ pub fn create_transaction(from: String, to: String, amount: i32) {
 ///
 /// # Args
 /// ```
-/// name: String -> name on the miners account
+/// store: &mut dyn ChainStore -> storage backend holding the chain
+/// name: &str                 -> name on the miners account
+/// config: &ChainConfig       -> configuration setting the mining difficulty
+/// keystore: &mut Keystore    -> keystore unlocking the miners private key
 /// ```
 /// # Returns
-///
-/// Nothing
-pub fn mine_block(name: String) {
+/// ```
+/// Result<Block, MockchainError>
+/// ```
+pub fn mine_block(
+    store: &mut dyn ChainStore,
+    name: &str,
+    config: &ChainConfig,
+    keystore: &mut Keystore,
+) -> Result<Block, MockchainError> {
     Log::new(LogLevel::INFO, 8, None);
-    let mut base_data = FileOps::parse(BLOCKCHAIN_PATH.as_path());
-    let blockchain = match base_data["blockchain"].as_array_mut() {
-        Some(data) => data,
-        None => {
-            Log::new_panic(
-                LogLevel::ERROR,
-                2,
-                Some(vec!["blockchain.json".to_string()]),
-            );
-            panic!("Failed to read blockchain.json, has the data been modified or the file moved or deleted?");
-        }
+    let blocks = store.blocks()?;
+    let previous_hash = match blocks.last() {
+        Some(block) => block.hash.clone(),
+        None => return Err(MockchainError::Corrupt("blockchain.json".to_string())),
     };
 
-    let last_block = &blockchain[blockchain.len() - 1];
-    // components of Block hash
-    let mut nonce = 0;
-    let previous_hash = &last_block["hash"].to_string().replace("\"", "");
-    let mut base_data = FileOps::parse(TRANSACTIONS_PATH.as_path());
+    let height = blocks.len() as u64;
+    // drop pending transactions that are replayed, out of order or overspent,
+    // hold the ones waiting on a coinbase to mature and fill the block with
+    // the best paying of the rest
+    let (mut transactions, waiting) =
+        Mempool::select_for_block(store, config.max_block_size, config.coinbase_maturity)?;
+    // the coinbase pays the miner the subsidy plus fees and leads the block
+    let fees = Transaction::total_fees(&transactions)?;
+    let amount = Transaction::total(config.monetary_policy.subsidy(height), fees)?;
+    let coinbase = Transaction::coinbase(store, name, height, amount, keystore)?;
+    transactions.insert(0, coinbase);
+    Log::new(LogLevel::INFO, 17, Some(vec![amount.to_string()]));
     // set mining difficulty
-    let leading_zeros = String::from("0".repeat(2));
-    Log::new(
-        LogLevel::INFO,
-        9,
-        Some(vec![leading_zeros.len().to_string()]),
-    );
-    // get block hash
-    let mut hash = hash_block(
-        &nonce.to_string(),
-        &previous_hash.clone(),
-        &base_data.to_string(),
-    );
-
-    // compute the correct hash to mine a new Block (00...98de872911a5e etc)
-    while !hash.starts_with(&leading_zeros) {
-        nonce += 1;
-        hash = hash_block(
-            &nonce.to_string(),
-            &previous_hash.clone(),
-            &base_data.to_string(),
-        );
-    }
-    Log::new(LogLevel::INFO, 10, None);
-
-    // get the current timestamp
-    let timestamp = get_timestamp();
+    let difficulty = config.next_difficulty(&blocks);
+    Log::new(LogLevel::INFO, 9, Some(vec![difficulty.to_string()]));
 
-    // get the merkle root of this Blocks Transactions
-    let merkle_root = get_merkle_root(TRANSACTIONS_PATH.as_path());
+    // the merkle root binds the transactions to the header
+    let merkle_root = get_merkle_root(&transactions);
     Log::new(LogLevel::INFO, 11, None);
 
-    // pay all transactions
-    let transactions = match base_data["transactions"].as_array_mut() {
-        Some(data) => data,
-        None => {
-            Log::new_panic(
-                LogLevel::ERROR,
-                2,
-                Some(vec!["blockchain.json".to_string()]),
-            );
-            panic!("Failed to read blockchain.json, has the data been modified or the file moved or deleted?");
-        }
+    // components of Block hash
+    let mut header = BlockHeader {
+        version: BLOCK_VERSION,
+        height,
+        previous_hash,
+        merkle_root,
+        timestamp: get_timestamp(),
+        difficulty,
+        nonce: 0,
     };
+    // compute the correct hash to mine a new Block (00...98de872911a5e etc)
+    let hash = header.mine();
+    Log::new(LogLevel::INFO, 10, None);
 
-    for t in transactions {
-        let amount: i32;
-        if let Some(val) = t["amount"].as_i64() {
-            amount = val as i32;
-        } else {
-            Log::new_panic(LogLevel::ERROR, 16, None);
-            panic!("Failed to parse transaction amount while mining block");
-        };
-
-        if t["from_address"] == "REWARD" {
-            Log::new(LogLevel::INFO, 12, Some(vec![t["to_address"].to_string()]));
-            Wallet::update_balance(t["to_address"].to_string(), amount, "add");
+    // pay all transactions on top of the replayed chain
+    let mut state = ChainState::replay(&blocks);
+    for t in transactions.iter() {
+        state.apply(t);
+        if t.is_coinbase() {
+            Log::new(
+                LogLevel::INFO,
+                12,
+                Some(vec![t.amount.to_string(), t.to_address.clone()]),
+            );
         } else {
-            Wallet::update_balance(t["to_address"].to_string(), amount, "add");
-            Wallet::update_balance(t["from_address"].to_string(), amount, "subtract");
             Log::new(
                 LogLevel::INFO,
                 13,
                 Some(vec![
-                    t["amount"].to_string(),
-                    t["from_address"].to_string(),
-                    t["to_address"].to_string(),
+                    t.amount.to_string(),
+                    t.from_address.clone(),
+                    t.to_address.clone(),
                 ]),
             )
         }
@@ -246,139 +276,855 @@ pub fn mine_block(name: String) {
     Log::new(LogLevel::INFO, 14, None);
 
     let block = Block {
-        timestamp,
+        header,
         hash,
-        previous_hash: previous_hash.to_string(),
-        nonce,
-        transactions: base_data,
-        merkle_root,
+        transactions,
     };
 
-    FileOps::write(BLOCKCHAIN_PATH.as_path(), "blockchain", block);
+    store.write(
+        DataFile::Blockchain,
+        to_record(&block, DataFile::Blockchain)?,
+    )?;
     Log::new(LogLevel::INFO, 15, None);
-    Transaction::clear();
-    Log::new(LogLevel::INFO, 16, None);
-    Transaction::add_reward(name);
-    Log::new(LogLevel::INFO, 17, None);
+    // refresh the balances cached in wallets.json
+    state.write_wallets(store)?;
+    // transactions left out of the block keep waiting
+    store.overwrite(
+        DataFile::Transactions,
+        to_records(&waiting, DataFile::Transactions)?,
+    )?;
+    Log::new(LogLevel::INFO, 16, Some(vec![waiting.len().to_string()]));
     Log::new(LogLevel::INFO, 18, None);
+    Ok(block)
 }
 
-/// Verifies the integrity of the blockchain
+/// Verifies the integrity of the blockchain. Every
+/// Block and transaction is checked and each problem
+/// found is recorded, verification does not stop at
+/// the first failure
 ///
 /// # Visibility
 /// public
 ///
 /// # Args
-/// None
+/// ```
+/// store: &dyn ChainStore -> storage backend holding the chain
+/// config: &ChainConfig   -> configuration holding the monetary policy and coinbase maturity
+/// ```
 ///
 /// # Returns
 /// ```
-/// bool
+/// Result<VerificationReport, MockchainError>
 /// ```
-pub fn verify_chain() -> bool {
+pub fn verify_chain(
+    store: &dyn ChainStore,
+    config: &ChainConfig,
+) -> Result<VerificationReport, MockchainError> {
     Log::new(LogLevel::INFO, 26, None);
-    let mut bc_base_data = FileOps::parse(BLOCKCHAIN_PATH.as_path());
-    let blockchain = match bc_base_data["blockchain"].as_array_mut() {
-        Some(data) => data,
+    let blockchain = store.blocks()?;
+    let mut report = VerificationReport::new(blockchain.len());
+    let mut sequence = Sequence::default();
+    let mut state = ChainState::default();
+
+    // the first block must be the genesis block built from the spec
+    match blockchain.first() {
+        Some(genesis) => {
+            if !config.genesis.matches(genesis) {
+                Log::new(
+                    LogLevel::ERROR,
+                    61,
+                    Some(vec![config.genesis.chain_id.clone()]),
+                );
+                report.block_failure(
+                    FailureKind::BadGenesis,
+                    0,
+                    genesis,
+                    format!(
+                        "block does not match the genesis spec of chain '{}'",
+                        config.genesis.chain_id
+                    ),
+                );
+            }
+            // credit the genesis allocations
+            for transaction in genesis.transactions.iter() {
+                state.apply(transaction);
+            }
+        }
         None => {
-            Log::new_panic(
+            Log::new(
                 LogLevel::ERROR,
-                2,
-                Some(vec!["blockchain.json".to_string()]),
+                61,
+                Some(vec![config.genesis.chain_id.clone()]),
+            );
+            report.chain_failure(
+                FailureKind::BadGenesis,
+                String::from("chain has no genesis block"),
             );
-            panic!("Failed to read blockchain.json, has the data been modified or the file moved or deleted?");
         }
-    };
+    }
 
     // loop over each block in the chain
-    for i in 1..blockchain.len() {
-        let previous_block = blockchain[i - 1].clone();
-        let mut current_block = blockchain[i].clone();
+    for (i, pair) in blockchain.windows(2).enumerate() {
+        let (previous_block, current_block) = (&pair[0], &pair[1]);
+        let header = &current_block.header;
+        let height = i as u64 + 1;
+
+        // the header must record its place in the chain and a known layout
+        if header.height != height || header.version != BLOCK_VERSION {
+            Log::new(
+                LogLevel::ERROR,
+                55,
+                Some(vec![
+                    height.to_string(),
+                    header.height.to_string(),
+                    header.version.to_string(),
+                ]),
+            );
+            report.block_failure(
+                FailureKind::BadHeader,
+                height,
+                current_block,
+                format!(
+                    "header records height {} and version {}",
+                    header.height, header.version
+                ),
+            );
+        }
 
         // check hashes match for current record and previous block
-        if current_block["previous_hash"] != previous_block["hash"] {
+        if header.previous_hash != previous_block.hash {
+            Log::new(LogLevel::ERROR, 57, Some(vec![height.to_string()]));
+            report.block_failure(
+                FailureKind::BrokenLink,
+                height,
+                current_block,
+                format!(
+                    "previous hash {} does not match the hash of block {}",
+                    header.previous_hash,
+                    height - 1
+                ),
+            );
+        }
+
+        // validate the current block hash, computed over the header only
+        let block_hash = header.hash();
+        if current_block.hash != block_hash {
+            Log::new(LogLevel::ERROR, 27, Some(vec![height.to_string()]));
+            report.block_failure(
+                FailureKind::BadBlockHash,
+                height,
+                current_block,
+                format!("header hashes to {}", block_hash),
+            );
+        }
+
+        // the recorded difficulty must be the one the chain expects,
+        // so a forged block cannot lower it to skip the work
+        let difficulty = config.next_difficulty(&blockchain[..height as usize]);
+        if header.difficulty != difficulty {
             Log::new(
                 LogLevel::ERROR,
-                27,
-                Some(vec![current_block["timestamp"].to_string()]),
+                63,
+                Some(vec![
+                    height.to_string(),
+                    header.difficulty.to_string(),
+                    difficulty.to_string(),
+                ]),
+            );
+            report.block_failure(
+                FailureKind::BadProofOfWork,
+                height,
+                current_block,
+                format!(
+                    "header records difficulty {} but the chain expects {}",
+                    header.difficulty, difficulty
+                ),
+            );
+        } else if !meets_difficulty(&current_block.hash, header.difficulty) {
+            // check the hash carries the work recorded for the block
+            Log::new(
+                LogLevel::ERROR,
+                35,
+                Some(vec![height.to_string(), header.difficulty.to_string()]),
+            );
+            report.block_failure(
+                FailureKind::BadProofOfWork,
+                height,
+                current_block,
+                format!(
+                    "hash does not start with {} leading zeros",
+                    header.difficulty
+                ),
             );
-            return false;
         }
 
-        // validate the current block hash
-        let hash = hash_block(
-            &current_block["nonce"].to_string(),
-            &current_block["previous_hash"].to_string().replace("\"", ""),
-            &current_block["transactions"].to_string(),
-        );
+        // rebuild the merkle root from the transactions in the block,
+        // binding the body to the hashed header
+        let merkle_root = get_merkle_root(&current_block.transactions);
+        if header.merkle_root != merkle_root {
+            Log::new(LogLevel::ERROR, 36, Some(vec![height.to_string()]));
+            report.block_failure(
+                FailureKind::MerkleMismatch,
+                height,
+                current_block,
+                format!("transactions give a merkle root of {}", merkle_root),
+            );
+        }
 
-        if current_block["hash"].to_string().replace("\"", "") != hash {
+        // blocks are never timestamped before the block they follow
+        let timestamps = (
+            DateTime::parse_from_rfc3339(&previous_block.header.timestamp),
+            DateTime::parse_from_rfc3339(&header.timestamp),
+        );
+        if !matches!(timestamps, (Ok(previous), Ok(current)) if current >= previous) {
             Log::new(
                 LogLevel::ERROR,
-                27,
-                Some(vec![current_block["timestamp"].to_string()]),
+                58,
+                Some(vec![height.to_string(), header.timestamp.clone()]),
+            );
+            report.block_failure(
+                FailureKind::TimestampRegression,
+                height,
+                current_block,
+                format!(
+                    "timestamp {} is before {} of block {}",
+                    header.timestamp,
+                    previous_block.header.timestamp,
+                    height - 1
+                ),
             );
-            return false;
         }
 
-        // validate transactions
-        let transactions = match current_block["transactions"]["transactions"].as_array_mut() {
-            Some(data) => data,
-            None => {
-                Log::new_panic(
-                    LogLevel::ERROR,
-                    2,
-                    Some(vec![
-                        "transactions from block in blockchain.json".to_string()
-                    ]),
+        // exactly one coinbase leads the block, paying the subsidy
+        // of the schedule plus the fees of the block
+        let coinbases = current_block
+            .transactions
+            .iter()
+            .filter(|t| t.is_coinbase())
+            .count();
+        match current_block.transactions.first() {
+            Some(coinbase) if coinbase.is_coinbase() && coinbases == 1 => {
+                let subsidy = config.monetary_policy.subsidy(height);
+                // fees overflowing an i32 leave no amount the coinbase can pay
+                let expected = Transaction::total_fees(&current_block.transactions)
+                    .and_then(|fees| Transaction::total(subsidy, fees))
+                    .ok();
+                if coinbase.nonce != height || Some(coinbase.amount) != expected {
+                    let expected = match expected {
+                        Some(expected) => expected.to_string(),
+                        None => String::from("no amount as the fees overflow"),
+                    };
+                    Log::new(
+                        LogLevel::ERROR,
+                        50,
+                        Some(vec![
+                            coinbase.hash.clone(),
+                            coinbase.amount.to_string(),
+                            expected.clone(),
+                        ]),
+                    );
+                    report.transaction_failure(
+                        FailureKind::BadCoinbase,
+                        height,
+                        current_block,
+                        0,
+                        format!(
+                            "coinbase pays {} tokens but the monetary policy allows {}",
+                            coinbase.amount, expected
+                        ),
+                    );
+                }
+            }
+            _ => {
+                Log::new(LogLevel::ERROR, 52, Some(vec![height.to_string()]));
+                report.block_failure(
+                    FailureKind::BadCoinbase,
+                    height,
+                    current_block,
+                    format!(
+                        "block holds {} coinbases and must start with exactly one",
+                        coinbases
+                    ),
                 );
-                panic!("Failed to read transactions from block in blockchain.json, has the data been modified?");
             }
-        };
+        }
 
-        for j in 0..transactions.len() {
+        // validate transactions
+        for (index, transaction) in current_block.transactions.iter().enumerate() {
             // validate current transaction hash
             let t_hash = hash_transaction(
-                &transactions[j]["from_address"]
-                    .to_string()
-                    .replace("\"", ""),
-                &transactions[j]["to_address"].to_string().replace("\"", ""),
-                &transactions[j]["amount"].to_string(),
+                &transaction.from_address,
+                &transaction.to_address,
+                transaction.amount,
+                transaction.fee,
+                transaction.nonce,
+                &transaction.timestamp,
             );
 
-            if transactions[j]["hash"] != t_hash.clone() {
-                Log::new(LogLevel::ERROR, 29, None);
-                return false;
+            if transaction.hash != t_hash {
+                Log::new(
+                    LogLevel::ERROR,
+                    29,
+                    Some(vec![index.to_string(), height.to_string()]),
+                );
+                report.transaction_failure(
+                    FailureKind::BadTransactionHash,
+                    height,
+                    current_block,
+                    index,
+                    format!("transaction data hashes to {}", t_hash),
+                );
             }
 
-            // get signing key for this transaction
-            let mut sd_base_data = FileOps::parse(SIGNING_DATA_PATH.as_path());
-            let signing_data = match sd_base_data["signing_data"].as_array_mut() {
-                Some(data) => data,
-                None => {
-                    Log::new_panic(LogLevel::ERROR, 2, Some(vec!["signing.json".to_string()]));
-                    panic!("Failed to read signing.json, has the data been modified or the file moved or deleted?");
-                }
+            // reject replayed hashes and nonces out of sequence
+            if !sequence.accept(transaction) {
+                Log::new(LogLevel::ERROR, 39, Some(vec![transaction.hash.clone()]));
+                report.transaction_failure(
+                    FailureKind::BadNonce,
+                    height,
+                    current_block,
+                    index,
+                    format!(
+                        "transaction is replayed or nonce {} from {} is out of order",
+                        transaction.nonce, transaction.from_address
+                    ),
+                );
+            }
+
+            // a coinbase cannot be spent until it matures
+            let from = &transaction.from_address;
+            let immature = transaction.cost().is_ok_and(|cost| {
+                state.balance(from) >= cost
+                    && state.spendable(from, height, config.coinbase_maturity) < cost
+            });
+            if !transaction.is_coinbase() && immature {
+                Log::new(LogLevel::ERROR, 53, Some(vec![transaction.hash.clone()]));
+                report.transaction_failure(
+                    FailureKind::ImmatureCoinbase,
+                    height,
+                    current_block,
+                    index,
+                    format!(
+                        "{} spends coinbase tokens before they mature",
+                        transaction.from_address
+                    ),
+                );
+            }
+
+            // replay the transfer and reject overdrawn senders
+            if !state.apply(transaction) {
+                Log::new(LogLevel::ERROR, 42, Some(vec![transaction.hash.clone()]));
+                report.transaction_failure(
+                    FailureKind::Overdrawn,
+                    height,
+                    current_block,
+                    index,
+                    format!(
+                        "{} cannot cover {} + {} tokens",
+                        transaction.from_address, transaction.amount, transaction.fee
+                    ),
+                );
+            }
+
+            // coinbases are signed by the miner receiving them, every
+            // other transaction by the owner of the sending address
+            let signer = if transaction.is_coinbase() {
+                &transaction.to_address
+            } else {
+                &transaction.from_address
             };
 
-            // verify each hash using the signing key
-            for s in signing_data {
-                if s["hash"] == t_hash.clone() {
-                    let (signature, signing_key) = KeyPair::extract(
-                        s["signature"].to_string().replace("\"", ""),
-                        s["signing_key"].to_string().replace("\"", ""),
-                    );
-                    if !KeyPair::verify(signature, signing_key, t_hash.clone()) {
-                        Log::new(LogLevel::ERROR, 31, Some(vec![s["name"].to_string()]));
-                        return false;
-                    }
+            // the public key carried by the transaction must hash to
+            // the signers address
+            let public_key = &transaction.public_key;
+            if to_address(public_key).ok().as_ref() != Some(signer) {
+                Log::new(LogLevel::ERROR, 49, Some(vec![transaction.hash.clone()]));
+                report.transaction_failure(
+                    FailureKind::KeyMismatch,
+                    height,
+                    current_block,
+                    index,
+                    format!("public key does not hash to {}", signer),
+                );
+            }
+
+            // verify the hash against the public key in the transaction,
+            // a signature or key that cannot be decoded fails verification
+            let verified = match KeyPair::extract(&transaction.signature, public_key) {
+                Ok((signature, verifying_key)) => {
+                    KeyPair::verify(&signature, &verifying_key, &t_hash)
                 }
+                Err(_) => false,
+            };
+            if !verified {
+                Log::new(
+                    LogLevel::ERROR,
+                    31,
+                    Some(vec![height.to_string(), public_key.clone()]),
+                );
+                report.transaction_failure(
+                    FailureKind::BadSignature,
+                    height,
+                    current_block,
+                    index,
+                    String::from("signature does not verify against the transaction hash"),
+                );
             }
         }
     }
+    // the balances cached in wallets.json must match the replayed chain
+    for m in state.mismatches(&store.wallets()?) {
+        Log::new(
+            LogLevel::ERROR,
+            43,
+            Some(vec![
+                m.name.clone(),
+                m.stored.to_string(),
+                m.derived.to_string(),
+            ]),
+        );
+        report.chain_failure(
+            FailureKind::BalanceMismatch,
+            format!(
+                "balance of '{}' in 'wallets.json' is {} but replaying the chain gives {}",
+                m.name, m.stored, m.derived
+            ),
+        );
+    }
+
+    if !report.valid {
+        Log::new(
+            LogLevel::ERROR,
+            60,
+            Some(vec![report.failures.len().to_string()]),
+        );
+        return Ok(report);
+    }
+    Log::new(LogLevel::INFO, 62, None);
+    Log::new(LogLevel::INFO, 56, None);
     Log::new(LogLevel::INFO, 28, None);
+    Log::new(LogLevel::INFO, 37, None);
+    Log::new(LogLevel::INFO, 59, None);
     Log::new(LogLevel::INFO, 30, None);
+    Log::new(LogLevel::INFO, 40, None);
+    Log::new(LogLevel::INFO, 44, None);
+    Log::new(LogLevel::INFO, 51, None);
+    Log::new(LogLevel::INFO, 54, None);
     Log::new(LogLevel::INFO, 32, None);
     Log::new(LogLevel::INFO, 33, None);
-    true
+    Ok(report)
+}
+
+// Testing
+#[cfg(test)]
+mod test_helpers {
+    use super::*;
+
+    use crate::mods::{
+        config::MonetaryPolicy, genesis::GenesisSpec, memory::MemoryStore, store::to_records,
+        transaction::COINBASE,
+    };
+
+    fn setup() -> MemoryStore {
+        let mut store = MemoryStore::new();
+        let mut keystore = Keystore::new(true);
+        store.init(false).unwrap();
+        Block::add_genesis_block(&mut store, &GenesisSpec::default()).unwrap();
+        create_wallet(&mut store, "alice", &mut keystore).unwrap();
+        create_wallet(&mut store, "bob", &mut keystore).unwrap();
+        store
+    }
+
+    #[test]
+    fn test_mine_and_transact_in_memory() {
+        let mut store = setup();
+        let mut keystore = Keystore::new(true);
+        let config = ChainConfig::default();
+
+        // the coinbase of the first block can be spent from the third
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+        mine_block(&mut store, "bob", &config, &mut keystore).unwrap();
+        assert_eq!(50, Wallet::get_balance(&store, "alice").unwrap());
+
+        create_transaction(&mut store, "alice", "bob", 20, 0, &mut keystore).unwrap();
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+
+        assert_eq!(80, Wallet::get_balance(&store, "alice").unwrap());
+        assert_eq!(70, Wallet::get_balance(&store, "bob").unwrap());
+        assert!(verify_chain(&store, &config).unwrap().valid);
+    }
+
+    #[test]
+    fn test_verify_chain_detects_tampering() {
+        let mut store = setup();
+        let mut keystore = Keystore::new(true);
+        let config = ChainConfig::default();
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+        mine_block(&mut store, "bob", &config, &mut keystore).unwrap();
+
+        // inflate the coinbase paid to bob inside the last block
+        let mut base_data = store.parse(DataFile::Blockchain).unwrap();
+        base_data["blockchain"][2]["transactions"][0]["amount"] = serde_json::json!(5000);
+        store.overwrite(DataFile::Blockchain, base_data).unwrap();
+
+        assert_eq!(
+            vec![
+                FailureKind::MerkleMismatch,
+                FailureKind::BadCoinbase,
+                FailureKind::BadTransactionHash,
+                FailureKind::BadSignature,
+                FailureKind::BalanceMismatch
+            ],
+            verify_chain(&store, &config).unwrap().kinds()
+        );
+    }
+
+    #[test]
+    fn test_verify_chain_checks_public_key_matches_address() {
+        let mut store = setup();
+        let mut keystore = Keystore::new(true);
+        let config = ChainConfig::default();
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+        mine_block(&mut store, "bob", &config, &mut keystore).unwrap();
+
+        // bob signs a transfer out of alice's address with his own key
+        let mut forged =
+            create_transaction(&mut store, "alice", "bob", 20, 0, &mut keystore).unwrap();
+        forged.public_key = KeyPair::get_key(&store, "bob", "public").unwrap();
+        let private_key = KeyPair::get_key(&store, "bob", "private").unwrap();
+        forged.signature = KeyPair::sign(&forged.hash, &private_key).unwrap();
+        store
+            .overwrite(
+                DataFile::Transactions,
+                to_records(&[forged], DataFile::Transactions).unwrap(),
+            )
+            .unwrap();
+        mine_block(&mut store, "bob", &config, &mut keystore).unwrap();
+
+        assert_eq!(
+            vec![FailureKind::KeyMismatch],
+            verify_chain(&store, &config).unwrap().kinds()
+        );
+    }
+
+    #[test]
+    fn test_verify_chain_checks_coinbase_schedule() {
+        let mut store = setup();
+        let mut keystore = Keystore::new(true);
+        let config = ChainConfig {
+            monetary_policy: MonetaryPolicy {
+                initial_reward: 50,
+                halving_interval: 1,
+                max_supply: 1000,
+            },
+            ..ChainConfig::default()
+        };
+        for _ in 0..3 {
+            mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+        }
+        assert_eq!(12, store.blocks().unwrap()[3].transactions[0].amount);
+        assert!(verify_chain(&store, &config).unwrap().valid);
+        // the chain does not follow the default schedule
+        assert_eq!(
+            vec![FailureKind::BadCoinbase, FailureKind::BadCoinbase],
+            verify_chain(&store, &ChainConfig::default())
+                .unwrap()
+                .kinds()
+        );
+
+        // a miner paying itself more than the schedule allows, re-signed
+        // and re-hashed so only the amount gives it away
+        let mut blocks = store.blocks().unwrap();
+        let block = blocks.last_mut().unwrap();
+        let coinbase = &mut block.transactions[0];
+        coinbase.amount = 5000;
+        coinbase.hash = hash_transaction(
+            COINBASE,
+            &coinbase.to_address,
+            coinbase.amount,
+            0,
+            coinbase.nonce,
+            &coinbase.timestamp,
+        );
+        let private_key = KeyPair::get_key(&store, "alice", "private").unwrap();
+        coinbase.signature = KeyPair::sign(&coinbase.hash, &private_key).unwrap();
+        block.header.merkle_root = get_merkle_root(&block.transactions);
+        block.hash = block.header.mine();
+        store
+            .overwrite(
+                DataFile::Blockchain,
+                to_records(&blocks, DataFile::Blockchain).unwrap(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            vec![FailureKind::BadCoinbase, FailureKind::BalanceMismatch],
+            verify_chain(&store, &config).unwrap().kinds()
+        );
+    }
+
+    #[test]
+    fn test_verify_chain_checks_coinbase_maturity() {
+        let mut store = setup();
+        let mut keystore = Keystore::new(true);
+        let config = ChainConfig::default();
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+
+        // the coinbase of the first block cannot be spent in the second
+        // so the transfer waits until it matures
+        let transfer =
+            create_transaction(&mut store, "alice", "bob", 20, 0, &mut keystore).unwrap();
+        let block = mine_block(&mut store, "bob", &config, &mut keystore).unwrap();
+        assert_eq!(1, block.transactions.len());
+        assert_eq!(transfer.hash, store.transactions().unwrap()[0].hash);
+        let block = mine_block(&mut store, "bob", &config, &mut keystore).unwrap();
+        assert_eq!(transfer.hash, block.transactions[1].hash);
+        assert!(store.transactions().unwrap().is_empty());
+        assert!(verify_chain(&store, &config).unwrap().valid);
+
+        // a chain mined with a shorter maturity spends it too early
+        let hasty = ChainConfig {
+            coinbase_maturity: 1,
+            ..ChainConfig::default()
+        };
+        let mut store = setup();
+        mine_block(&mut store, "alice", &hasty, &mut keystore).unwrap();
+        create_transaction(&mut store, "alice", "bob", 20, 0, &mut keystore).unwrap();
+        let block = mine_block(&mut store, "bob", &hasty, &mut keystore).unwrap();
+        assert_eq!(2, block.transactions.len());
+        assert!(verify_chain(&store, &hasty).unwrap().valid);
+        assert_eq!(
+            vec![FailureKind::ImmatureCoinbase],
+            verify_chain(&store, &config).unwrap().kinds()
+        );
+    }
+
+    #[test]
+    fn test_verify_chain_checks_recorded_difficulty() {
+        let mut store = setup();
+        let mut keystore = Keystore::new(true);
+        let config = ChainConfig {
+            difficulty: 1,
+            retarget: None,
+            ..ChainConfig::default()
+        };
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+        assert!(verify_chain(&store, &config).unwrap().valid);
+
+        // claim more work than the block hash carries
+        let mut blocks = store.blocks().unwrap();
+        blocks[1].header.difficulty = 64;
+        blocks[1].hash = blocks[1].header.hash();
+        store
+            .overwrite(
+                DataFile::Blockchain,
+                to_records(&blocks, DataFile::Blockchain).unwrap(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            vec![FailureKind::BadProofOfWork],
+            verify_chain(&store, &config).unwrap().kinds()
+        );
+
+        // lower the difficulty so the header can be re-hashed without work
+        blocks[1].header.difficulty = 0;
+        blocks[1].hash = blocks[1].header.hash();
+        store
+            .overwrite(
+                DataFile::Blockchain,
+                to_records(&blocks, DataFile::Blockchain).unwrap(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            vec![FailureKind::BadProofOfWork],
+            verify_chain(&store, &config).unwrap().kinds()
+        );
+    }
+
+    #[test]
+    fn test_verify_chain_reports_every_failure() {
+        let mut store = setup();
+        let mut keystore = Keystore::new(true);
+        let config = ChainConfig::default();
+        for _ in 0..3 {
+            mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+        }
+
+        // backdate the second block, re-hashed so only the timestamp
+        // gives it away, which breaks the link from the third
+        let mut blocks = store.blocks().unwrap();
+        blocks[2].header.timestamp = String::from("2000-01-01T00:00:00+00:00");
+        blocks[2].hash = blocks[2].header.mine();
+        store
+            .overwrite(
+                DataFile::Blockchain,
+                to_records(&blocks, DataFile::Blockchain).unwrap(),
+            )
+            .unwrap();
+
+        let report = verify_chain(&store, &config).unwrap();
+        assert!(!report.valid);
+        assert_eq!(4, report.blocks);
+        assert_eq!(
+            vec![FailureKind::TimestampRegression, FailureKind::BrokenLink],
+            report.kinds()
+        );
+        assert_eq!(Some(2), report.failures[0].height);
+        assert_eq!(Some(blocks[2].hash.clone()), report.failures[0].block_hash);
+        assert_eq!(Some(3), report.failures[1].height);
+        assert_eq!(None, report.failures[1].transaction_index);
+    }
+
+    #[test]
+    fn test_verify_chain_checks_genesis() {
+        let mut store = setup();
+        let mut keystore = Keystore::new(true);
+        let config = ChainConfig::default();
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+        assert!(verify_chain(&store, &config).unwrap().valid);
+
+        // swap in the genesis block of another chain
+        let mut blocks = store.blocks().unwrap();
+        blocks[0] = GenesisSpec {
+            chain_id: String::from("another"),
+            ..GenesisSpec::default()
+        }
+        .block()
+        .unwrap();
+        store
+            .overwrite(
+                DataFile::Blockchain,
+                to_records(&blocks, DataFile::Blockchain).unwrap(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            vec![FailureKind::BadGenesis, FailureKind::BrokenLink],
+            verify_chain(&store, &config).unwrap().kinds()
+        );
+    }
+
+    #[test]
+    fn test_verify_chain_checks_merkle_root() {
+        let mut store = setup();
+        let mut keystore = Keystore::new(true);
+        let config = ChainConfig::default();
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+        mine_block(&mut store, "bob", &config, &mut keystore).unwrap();
+
+        // a header re-hashed over a root that does not match the body
+        let mut blocks = store.blocks().unwrap();
+        let block = blocks.last_mut().unwrap();
+        block.header.merkle_root = "0".repeat(64);
+        block.hash = block.header.mine();
+        store
+            .overwrite(
+                DataFile::Blockchain,
+                to_records(&blocks, DataFile::Blockchain).unwrap(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            vec![FailureKind::MerkleMismatch],
+            verify_chain(&store, &config).unwrap().kinds()
+        );
+    }
+
+    #[test]
+    fn test_blocks_record_their_height() {
+        let mut store = setup();
+        let mut keystore = Keystore::new(true);
+        let config = ChainConfig::default();
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+        let block = mine_block(&mut store, "bob", &config, &mut keystore).unwrap();
+        assert_eq!(2, block.header.height);
+        assert_eq!(BLOCK_VERSION, block.header.version);
+        assert_eq!(block.hash, Block::at_height(&store, 2).unwrap().hash);
+        assert!(matches!(
+            Block::at_height(&store, 3),
+            Err(MockchainError::BlockNotFound(3))
+        ));
+
+        // a block moved to another height no longer fits the chain
+        let mut blocks = store.blocks().unwrap();
+        let block = blocks.last_mut().unwrap();
+        block.header.height = 5;
+        block.hash = block.header.mine();
+        store
+            .overwrite(
+                DataFile::Blockchain,
+                to_records(&blocks, DataFile::Blockchain).unwrap(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            vec![FailureKind::BadHeader],
+            verify_chain(&store, &config).unwrap().kinds()
+        );
+    }
+
+    #[test]
+    fn test_mine_block_drops_replayed_transactions() {
+        let mut store = setup();
+        let mut keystore = Keystore::new(true);
+        let config = ChainConfig::default();
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+        mine_block(&mut store, "bob", &config, &mut keystore).unwrap();
+
+        // the same signed transaction submitted twice
+        let transaction =
+            create_transaction(&mut store, "alice", "bob", 20, 0, &mut keystore).unwrap();
+        assert_eq!(0, transaction.nonce);
+        store
+            .write(
+                DataFile::Transactions,
+                to_record(&transaction, DataFile::Transactions).unwrap(),
+            )
+            .unwrap();
+        let block = mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+
+        assert_eq!(2, block.transactions.len());
+        assert_eq!(80, Wallet::get_balance(&store, "alice").unwrap());
+        assert_eq!(
+            1,
+            Transaction::next_nonce(&store, &transaction.from_address).unwrap()
+        );
+        assert!(verify_chain(&store, &config).unwrap().valid);
+    }
+
+    #[test]
+    fn test_verify_chain_detects_replay() {
+        let mut store = setup();
+        let mut keystore = Keystore::new(true);
+        let config = ChainConfig::default();
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+        mine_block(&mut store, "bob", &config, &mut keystore).unwrap();
+        create_transaction(&mut store, "alice", "bob", 20, 0, &mut keystore).unwrap();
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+
+        // rebuild the last block with its transfer included twice
+        let mut blocks = store.blocks().unwrap();
+        let block = blocks.last_mut().unwrap();
+        let transfer = block.transactions[1].clone();
+        block.transactions.push(transfer);
+        block.header.merkle_root = get_merkle_root(&block.transactions);
+        block.hash = block.header.mine();
+        store
+            .overwrite(
+                DataFile::Blockchain,
+                to_records(&blocks, DataFile::Blockchain).unwrap(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            vec![
+                FailureKind::BadNonce,
+                FailureKind::BalanceMismatch,
+                FailureKind::BalanceMismatch
+            ],
+            verify_chain(&store, &config).unwrap().kinds()
+        );
+    }
 }
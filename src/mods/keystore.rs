@@ -0,0 +1,506 @@
+/*
+    Password based encryption of private keys at rest
+*/
+
+// 3rd party crates
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use hex::{decode, encode};
+use rand_core::{OsRng, RngCore};
+use scrypt::{scrypt, Params};
+use serde::{Deserialize, Serialize};
+
+// imports
+use super::{
+    crypto::KeyPair,
+    error::MockchainError,
+    log::{Log, LogLevel},
+    store::ChainStore,
+};
+
+// scrypt cost parameters for new key pairs (N = 2^15)
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+/// Parameters of the scrypt key derivation
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// dklen: usize -> length of the derived key in bytes
+/// n: u32       -> CPU and memory cost, a power of 2
+/// r: u32       -> block size
+/// p: u32       -> parallelisation
+/// salt: String -> random hex salt
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KdfParams {
+    pub dklen: usize,
+    pub n: u32,
+    pub r: u32,
+    pub p: u32,
+    pub salt: String,
+}
+
+/// Parameters of the cipher
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// nonce: String -> random hex nonce
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CipherParams {
+    pub nonce: String,
+}
+
+/// Encrypted private key, laid out after the crypto
+/// section of an Ethereum v3 keystore. The key is
+/// derived from a password with scrypt and the
+/// private key sealed with AES-256-GCM, which also
+/// authenticates the public key it belongs to
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// cipher: String             -> "aes-256-gcm"
+/// ciphertext: String         -> hex private key ciphertext and tag
+/// cipherparams: CipherParams -> cipher parameters
+/// kdf: String                -> "scrypt"
+/// kdfparams: KdfParams       -> key derivation parameters
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct KeyCrypto {
+    pub cipher: String,
+    pub ciphertext: String,
+    pub cipherparams: CipherParams,
+    pub kdf: String,
+    pub kdfparams: KdfParams,
+}
+
+impl KeyCrypto {
+    /// Encrypts a private key with a password
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// private_key: &str -> hex private key to encrypt
+    /// public_key: &str  -> public key authenticated alongside it
+    /// password: &str    -> password to derive the key from
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<KeyCrypto, MockchainError>
+    /// ```
+    pub fn encrypt(
+        private_key: &str,
+        public_key: &str,
+        password: &str,
+    ) -> Result<KeyCrypto, MockchainError> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+        let kdfparams = KdfParams {
+            dklen: KEY_LEN,
+            n: 1 << SCRYPT_LOG_N,
+            r: SCRYPT_R,
+            p: SCRYPT_P,
+            salt: encode(salt),
+        };
+        let cipher = kdfparams.cipher(password)?;
+        let payload = Payload {
+            msg: private_key.as_bytes(),
+            aad: public_key.as_bytes(),
+        };
+        let ciphertext = match cipher.encrypt(Nonce::from_slice(&nonce), payload) {
+            Ok(ciphertext) => ciphertext,
+            Err(_) => {
+                return Err(MockchainError::Crypto(
+                    "Failed to encrypt private key".to_string(),
+                ))
+            }
+        };
+        Ok(KeyCrypto {
+            cipher: String::from("aes-256-gcm"),
+            ciphertext: encode(ciphertext),
+            cipherparams: CipherParams {
+                nonce: encode(nonce),
+            },
+            kdf: String::from("scrypt"),
+            kdfparams,
+        })
+    }
+
+    /// Decrypts a private key with a password
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str       -> name on the key pair, for errors
+    /// public_key: &str -> public key the private key belongs to
+    /// password: &str   -> password the key was encrypted with
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<String, MockchainError> -> WrongPassword if the key cannot be opened
+    /// ```
+    pub fn decrypt(
+        &self,
+        name: &str,
+        public_key: &str,
+        password: &str,
+    ) -> Result<String, MockchainError> {
+        if self.cipher != "aes-256-gcm" || self.kdf != "scrypt" {
+            return Err(MockchainError::Crypto(format!(
+                "Unsupported keystore for '{}': {} with {}",
+                name, self.cipher, self.kdf
+            )));
+        }
+        let nonce = decode_hex(&self.cipherparams.nonce, "nonce")?;
+        if nonce.len() != NONCE_LEN {
+            return Err(MockchainError::Crypto(format!(
+                "Keystore nonce for '{}' must be {} bytes",
+                name, NONCE_LEN
+            )));
+        }
+        let ciphertext = decode_hex(&self.ciphertext, "ciphertext")?;
+        let cipher = self.kdfparams.cipher(password)?;
+        let payload = Payload {
+            msg: &ciphertext,
+            aad: public_key.as_bytes(),
+        };
+        // a failed tag check means the password or the record is wrong
+        match cipher.decrypt(Nonce::from_slice(&nonce), payload) {
+            Ok(plaintext) => match String::from_utf8(plaintext) {
+                Ok(private_key) => Ok(private_key),
+                Err(_) => Err(MockchainError::WrongPassword(name.to_string())),
+            },
+            Err(_) => Err(MockchainError::WrongPassword(name.to_string())),
+        }
+    }
+}
+
+impl KdfParams {
+    /// Derives the cipher key from a password
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// password: &str -> password to derive the key from
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Aes256Gcm, MockchainError>
+    /// ```
+    fn cipher(&self, password: &str) -> Result<Aes256Gcm, MockchainError> {
+        if !self.n.is_power_of_two() || self.dklen != KEY_LEN {
+            return Err(MockchainError::Crypto(format!(
+                "Unsupported scrypt parameters n = {}, dklen = {}",
+                self.n, self.dklen
+            )));
+        }
+        let params = match Params::new(self.n.trailing_zeros() as u8, self.r, self.p, KEY_LEN) {
+            Ok(params) => params,
+            Err(e) => {
+                return Err(MockchainError::Crypto(format!(
+                    "Invalid scrypt parameters: {}",
+                    e
+                )))
+            }
+        };
+        let salt = decode_hex(&self.salt, "salt")?;
+        let mut key = [0u8; KEY_LEN];
+        if let Err(e) = scrypt(password.as_bytes(), &salt, &params, &mut key) {
+            return Err(MockchainError::Crypto(format!(
+                "Key derivation failed: {}",
+                e
+            )));
+        }
+        match Aes256Gcm::new_from_slice(&key) {
+            Ok(cipher) => Ok(cipher),
+            Err(_) => Err(MockchainError::Crypto(
+                "Invalid cipher key length".to_string(),
+            )),
+        }
+    }
+}
+
+/// Decodes a hex field of a keystore record
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// value: &str -> hex value
+/// field: &str -> name of the field, for errors
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<Vec<u8>, MockchainError>
+/// ```
+fn decode_hex(value: &str, field: &str) -> Result<Vec<u8>, MockchainError> {
+    match decode(value) {
+        Ok(bytes) => Ok(bytes),
+        Err(e) => Err(MockchainError::Crypto(format!(
+            "Keystore {} is not valid hex: {}",
+            field, e
+        ))),
+    }
+}
+
+/// Reasons a password is asked for
+///
+/// # Visibility
+/// public
+///
+/// # Variants
+/// ```
+/// New(String)    -> name on a key pair about to be encrypted
+/// Unlock(String) -> name on a key pair about to sign
+/// ```
+///
+/// # Derives
+/// ```
+/// Debug, Clone, PartialEq
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum PasswordRequest {
+    New(String),
+    Unlock(String),
+}
+
+/// Callback asked for a password, returning None if
+/// no password is given
+pub type PasswordPrompt = Box<dyn FnMut(&PasswordRequest) -> Option<String>>;
+
+/// Seals new key pairs and unlocks stored ones when
+/// they are needed to sign. Key pairs are encrypted
+/// unless plaintext keys have been opted into, and
+/// passwords are asked for through a PasswordPrompt
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// plaintext: bool                -> keep new private keys unencrypted
+/// prompt: Option<PasswordPrompt> -> callback asked for passwords
+/// ```
+pub struct Keystore {
+    plaintext: bool,
+    prompt: Option<PasswordPrompt>,
+}
+
+impl Keystore {
+    /// New instance of Keystore without a prompt
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// plaintext: bool -> keep new private keys unencrypted
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Keystore
+    /// ```
+    pub fn new(plaintext: bool) -> Keystore {
+        Keystore {
+            plaintext,
+            prompt: None,
+        }
+    }
+
+    /// Sets the callback asked for passwords
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// prompt: PasswordPrompt -> callback asked for passwords
+    /// ```
+    ///
+    /// # Returns
+    /// Nothing
+    pub fn set_prompt(&mut self, prompt: PasswordPrompt) {
+        self.prompt = Some(prompt);
+    }
+
+    /// Checks whether new private keys are kept unencrypted
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    pub fn plaintext(&self) -> bool {
+        self.plaintext
+    }
+
+    /// Asks the prompt for a password
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// request: PasswordRequest -> reason the password is needed
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<String, MockchainError> -> PasswordRequired if none is given
+    /// ```
+    fn ask(&mut self, request: PasswordRequest) -> Result<String, MockchainError> {
+        let password = self.prompt.as_mut().and_then(|prompt| prompt(&request));
+        match (password, request) {
+            (Some(password), _) if !password.is_empty() => Ok(password),
+            (_, PasswordRequest::New(name) | PasswordRequest::Unlock(name)) => {
+                Err(MockchainError::PasswordRequired(name))
+            }
+        }
+    }
+
+    /// Encrypts the private key of a new key pair
+    /// unless plaintext keys have been opted into
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// key_pair: KeyPair -> key pair holding a plaintext private key
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<KeyPair, MockchainError>
+    /// ```
+    pub fn seal(&mut self, mut key_pair: KeyPair) -> Result<KeyPair, MockchainError> {
+        if self.plaintext {
+            return Ok(key_pair);
+        }
+        let password = self.ask(PasswordRequest::New(key_pair.name.clone()))?;
+        key_pair.crypto = Some(KeyCrypto::encrypt(
+            &key_pair.private_key,
+            &key_pair.public_key,
+            &password,
+        )?);
+        key_pair.private_key = String::new();
+        Ok(key_pair)
+    }
+
+    /// Gets the private key of an account, asking for
+    /// its password if the key is encrypted
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// store: &dyn ChainStore -> storage backend holding the key pairs
+    /// name: &str             -> name of the account
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<String, MockchainError>
+    /// ```
+    pub fn private_key(
+        &mut self,
+        store: &dyn ChainStore,
+        name: &str,
+    ) -> Result<String, MockchainError> {
+        let key_pair = match store.keypairs()?.into_iter().find(|k| k.name == name) {
+            Some(key_pair) => key_pair,
+            None => return Err(MockchainError::KeyPairNotFound(name.to_string())),
+        };
+        let crypto = match &key_pair.crypto {
+            Some(crypto) => crypto,
+            None => return Ok(key_pair.private_key),
+        };
+        let password = self.ask(PasswordRequest::Unlock(name.to_string()))?;
+        let private_key = crypto.decrypt(name, &key_pair.public_key, &password);
+        if private_key.is_err() {
+            Log::new(LogLevel::WARNING, 48, Some(vec![name.to_string()]));
+        }
+        private_key
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_keystore {
+    use super::*;
+
+    #[test]
+    fn test_encrypted_key_round_trip() {
+        let key_pair = KeyPair::generate(String::from("alice"));
+        let crypto =
+            KeyCrypto::encrypt(&key_pair.private_key, &key_pair.public_key, "hunter2").unwrap();
+        assert!(!crypto.ciphertext.contains(&key_pair.private_key));
+        assert_eq!(
+            key_pair.private_key,
+            crypto
+                .decrypt("alice", &key_pair.public_key, "hunter2")
+                .unwrap()
+        );
+        assert!(matches!(
+            crypto.decrypt("alice", &key_pair.public_key, "hunter3"),
+            Err(MockchainError::WrongPassword(_))
+        ));
+
+        // the ciphertext only opens for the public key it was sealed with
+        let other = KeyPair::generate(String::from("bob"));
+        assert!(crypto
+            .decrypt("alice", &other.public_key, "hunter2")
+            .is_err());
+    }
+}
@@ -1,14 +1,19 @@
 // std library
 use std::fs;
 use std::io::prelude::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 
 // 3rd party crates
 use phf::phf_map;
 
 // imports
-use super::{helpers::get_timestamp, messaging::replace};
-use crate::{LOG_FILE_PATH, LOG_PATH};
+use super::{error::MockchainError, helpers::get_timestamp, messaging::replace};
+
+// log file entries are written to, set by Log::init
+lazy_static! {
+    static ref LOG_FILE_PATH: RwLock<Option<PathBuf>> = RwLock::new(None);
+}
 
 static LOG_MESSAGE_MAP: phf::Map<u8, &str> = phf_map! {
     1u8 => "Log file created",
@@ -22,12 +27,12 @@ static LOG_MESSAGE_MAP: phf::Map<u8, &str> = phf_map! {
     9u8 => "...mining started with a current difficulty of {}; block hash must start with <difficulty> leading zeros",
     10u8 => "...SHA256 block hash computed",
     11u8 => "...SHA256 merkle root of block transactions computed",
-    12u8 => "...block mining reward of 50 tokens paid to {}",
+    12u8 => "...coinbase of {} tokens paid to {}",
     13u8 => "...processing transaction; {} tokens sent from {} to {}",
-    14u8 => "...all pending transactions including rewards processed and paid",
+    14u8 => "...coinbase and selected pending transactions processed and paid",
     15u8 => "...new block appended to blockchain successfully",
-    16u8 => "...transaction data cleared",
-    17u8 => "...new reward transaction appended to 'transactions.json'",
+    16u8 => "...mined transactions cleared; {} transactions left pending",
+    17u8 => "...coinbase transaction of {} tokens signed by the miner and placed first in the block",
     18u8 => "...mining complete",
     19u8 => "Processing new transaction...",
     20u8 => "...read {}'s and {}'s public keys to start transaction",
@@ -37,22 +42,52 @@ static LOG_MESSAGE_MAP: phf::Map<u8, &str> = phf_map! {
     24u8 => "...signing data appended to 'signing.json'",
     25u8 => "...new transaction appended to 'transactions.json'",
     26u8 => "Starting blockchain verification...",
-    27u8 => "...bad SHA256 block hash in chain on or before {}; this chain has been tampered with, verification failed",
-    28u8 => "...block hashing is consistent",
-    29u8 => "...bad SHA256 transaction hash in chain; this chain has been tampered with, verification failed",
+    27u8 => "...bad SHA256 block hash at height {} in chain; this chain has been tampered with, verification failed",
+    28u8 => "...block header hashing is consistent",
+    29u8 => "...bad SHA256 hash for transaction {} of the block at height {} in chain; this chain has been tampered with, verification failed",
     30u8 => "...transaction hashing is consistent",
-    31u8 => "...bad transaction signature in chain; a transaction signed by {} could not be verified using ECDSA verification, verification failed",
+    31u8 => "...bad transaction signature at height {} in chain; a transaction signed by {} could not be verified using ECDSA verification, verification failed",
     32u8 => "...transaction signatures are consistent",
     33u8 => "...blockchain verification completed successfully",
+    34u8 => "...difficulty retargeted from {} to {}; average block time of {}s over the last {} blocks",
+    35u8 => "...block hash at height {} in chain does not meet its recorded difficulty of {}; verification failed",
+    36u8 => "...bad merkle root at height {} in chain; the root does not match the transactions in the block, verification failed",
+    37u8 => "...merkle roots are consistent",
+    38u8 => "...pending transaction {} dropped; nonce {} from {} is out of order or already used",
+    39u8 => "...transaction {} in chain is replayed or its nonce is out of order; verification failed",
+    40u8 => "...transaction nonces are consistent",
+    41u8 => "...pending transaction {} dropped; {} cannot cover {} tokens",
+    42u8 => "...transaction {} in chain spends more than its sender holds; verification failed",
+    43u8 => "...balance of '{}' in 'wallets.json' is {} but replaying the chain gives {}; verification failed",
+    44u8 => "...wallet balances match the chain",
+    45u8 => "Completed an interrupted write of {} data files from 'journal.json'",
+    46u8 => "Discarded an unreadable 'journal.json'; no data files were changed",
+    47u8 => "Chain data is locked by process {}; opened read-only",
+    48u8 => "Failed to unlock the key pair of '{}'",
+    49u8 => "...transaction {} in chain carries a public key that does not hash to its address; verification failed",
+    50u8 => "...coinbase {} in chain pays {} tokens but the monetary policy allows {}; verification failed",
+    51u8 => "...coinbase transactions follow the monetary policy",
+    52u8 => "...block at height {} in chain does not start with exactly one coinbase transaction; verification failed",
+    53u8 => "...transaction {} in chain spends coinbase tokens before they mature; verification failed",
+    54u8 => "...coinbase transactions are spent after they mature",
+    55u8 => "...block at height {} in chain records height {} with header version {}; verification failed",
+    56u8 => "...block heights and header versions are consistent",
+    57u8 => "...block at height {} in chain does not link to the hash of the block before it; verification failed",
+    58u8 => "...block at height {} in chain is timestamped {}, before the block it follows; verification failed",
+    59u8 => "...block timestamps never decrease",
+    60u8 => "...blockchain verification found {} problems; see the verification report",
+    61u8 => "...genesis block in chain does not match the genesis spec of chain '{}'; verification failed",
+    62u8 => "...genesis block matches the genesis spec",
+    63u8 => "...block at height {} in chain records difficulty {} but the chain expects {}; verification failed",
 };
 
 static LOG_PANIC_MAP: phf::Map<u8, &str> = phf_map! {
-    1u8 => "Unable to parse genesis block transaction to json_serde {}: {}",
+    1u8 => "Unable to parse block transactions to json_serde {}",
     2u8 => "Failed to read {}, has the data been modified or the file moved or deleted?",
-    3u8 => "Failed to decode private key while signing transaction; given key value: {}",
+    3u8 => "Failed to decode private key while signing transaction",
     4u8 => "Failed to decode signing key from bytes while signing transaction",
     5u8 => "Failed to decode and extract signature; given value: {}",
-    6u8 => "Failed to decode and extract signing key; given value: {}",
+    6u8 => "Failed to decode and extract verifying key; given value: {}",
     7u8 => "Error creating /.mockchain/data/ directory under $HOME",
     8u8 => "Error initialising data file: {}.json; could not parse given object to JSON string",
     9u8 => "Failed to write initialisation data to {}.json",
@@ -63,6 +98,7 @@ static LOG_PANIC_MAP: phf::Map<u8, &str> = phf_map! {
     14u8 => "Failed to parse empty transactions object to serde_json Value::String before clearing",
     15u8 => "Failed to parse private key from json_serde Value to &str while creating transaction",
     16u8 => "Failed to parse transaction amount while mining block",
+    17u8 => "Unexpected data found in {}",
 };
 
 /// Log enum with log level states
@@ -76,6 +112,7 @@ static LOG_PANIC_MAP: phf::Map<u8, &str> = phf_map! {
 /// Warning
 /// Error
 /// ```
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug)]
 pub enum LogLevel {
     INFO,
@@ -115,6 +152,7 @@ impl Log {
     ///
     /// # Returns
     /// Nothing
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(level: LogLevel, msg_key: u8, args: Option<Vec<String>>) {
         Log::record(&Log {
             level,
@@ -146,25 +184,39 @@ impl Log {
     }
 
     /// Initialise the log file if it doesn't already exist
+    /// and direct all further entries to it. Until this
+    /// has been called log entries are discarded
     ///
     /// # Visibility
     /// public
     ///
     /// # Args
-    /// None
+    /// ```
+    /// log_path: &Path -> directory to keep 'log.txt' in
+    /// ```
     ///
     /// # Returns
-    /// Nothing
-    pub fn init() {
-        if !Path::new(LOG_FILE_PATH.as_path()).exists() {
-            match fs::create_dir_all(LOG_PATH.as_path()) {
-                Ok(_) => {}
-                Err(e) => panic!("Error creating 'log.txt' file: {}", e),
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    pub fn init(log_path: &Path) -> Result<(), MockchainError> {
+        let log_file_path = log_path.join("log.txt");
+        if !log_file_path.exists() {
+            if let Err(e) = fs::create_dir_all(log_path) {
+                return Err(MockchainError::Io(format!(
+                    "Error creating 'log.txt' file: {}",
+                    e
+                )));
             };
         }
+        if let Ok(mut path) = LOG_FILE_PATH.write() {
+            *path = Some(log_file_path);
+        }
+        Ok(())
     }
 
-    /// Writes an entry to the log
+    /// Writes an entry to the log. Failing to write an
+    /// entry never interrupts the operation being logged
     ///
     /// # Visibility
     /// public
@@ -175,17 +227,27 @@ impl Log {
     /// # Returns
     /// Nothing
     pub fn record(&self) {
+        let log_file_path = match LOG_FILE_PATH.read() {
+            Ok(path) => match path.as_ref() {
+                Some(p) => p.clone(),
+                None => return,
+            },
+            Err(_) => return,
+        };
+
         let mut log_file = match fs::OpenOptions::new()
             .create(true)
             .append(true)
-            .open(LOG_FILE_PATH.as_path())
+            .open(&log_file_path)
         {
             Ok(handle) => handle,
-            Err(e) => panic!(
-                "Failed to open log file, has {:?} been moved or deleted? {}",
-                LOG_FILE_PATH.as_path(),
-                e
-            ),
+            Err(e) => {
+                eprintln!(
+                    "Failed to open log file, has {:?} been moved or deleted? {}",
+                    log_file_path, e
+                );
+                return;
+            }
         };
 
         let mut entry_text: String = String::new();
@@ -198,10 +260,9 @@ impl Log {
         entry_text.push_str(self.message.as_str());
 
         if let Err(e) = writeln!(log_file, "{}", entry_text) {
-            panic!(
+            eprintln!(
                 "Failed to write log entry, has {:?} been moved or deleted? {}",
-                LOG_FILE_PATH.as_path(),
-                e
+                log_file_path, e
             );
         }
     }
@@ -230,8 +291,8 @@ impl Log {
         let helper = |map: &phf::Map<u8, &str>| {
             if let Some(msg) = map.get(&key) {
                 match args {
-                    Some(vec) => return replace(msg.to_string(), vec),
-                    None => return msg.to_string(),
+                    Some(vec) => replace(msg.to_string(), vec),
+                    None => msg.to_string(),
                 }
             } else {
                 none_log.clone()
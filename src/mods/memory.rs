@@ -0,0 +1,112 @@
+// std library
+use std::collections::HashMap;
+
+// 3rd party crates
+use serde_json::Value;
+
+// imports
+use super::{
+    error::MockchainError,
+    log::{Log, LogLevel},
+    store::{ChainStore, DataFile},
+};
+
+/// In-memory ChainStore backend. Nothing is written to
+/// disk so a chain held here only lives as long as the
+/// store itself, which makes it useful for tests and
+/// throwaway simulations
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// data: HashMap<DataFile, Value>             -> data sets keyed by DataFile
+/// snapshot: Option<HashMap<DataFile, Value>> -> data sets as they were at begin
+/// ```
+///
+/// # Derives
+/// ```
+/// Debug, Default
+/// ```
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    data: HashMap<DataFile, Value>,
+    snapshot: Option<HashMap<DataFile, Value>>,
+}
+
+impl MemoryStore {
+    /// New, uninitialised instance of MemoryStore
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// MemoryStore
+    /// ```
+    pub fn new() -> MemoryStore {
+        MemoryStore::default()
+    }
+}
+
+impl ChainStore for MemoryStore {
+    fn exists(&self) -> bool {
+        self.data.contains_key(&DataFile::Blockchain)
+    }
+
+    fn init(&mut self, preserve_accounts: bool) -> Result<(), MockchainError> {
+        let mut files = vec![
+            DataFile::Blockchain,
+            DataFile::Transactions,
+            DataFile::SigningData,
+        ];
+        if !preserve_accounts || !self.data.contains_key(&DataFile::Wallets) {
+            files.push(DataFile::KeyPairs);
+            files.push(DataFile::Wallets);
+        }
+        for file in files {
+            self.data.insert(file, file.empty()?);
+        }
+        Ok(())
+    }
+
+    fn parse(&self, file: DataFile) -> Result<Value, MockchainError> {
+        match self.data.get(&file) {
+            Some(value) => Ok(value.clone()),
+            None => {
+                Log::new_panic(LogLevel::ERROR, 2, Some(vec![file.file_name().to_string()]));
+                Err(MockchainError::NotInitialised)
+            }
+        }
+    }
+
+    fn overwrite(&mut self, file: DataFile, data: Value) -> Result<(), MockchainError> {
+        self.data.insert(file, data);
+        Ok(())
+    }
+
+    fn begin(&mut self) -> Result<(), MockchainError> {
+        if self.snapshot.is_some() {
+            return Err(MockchainError::Io(
+                "A write is already in progress".to_string(),
+            ));
+        }
+        self.snapshot = Some(self.data.clone());
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), MockchainError> {
+        self.snapshot = None;
+        Ok(())
+    }
+
+    fn rollback(&mut self) {
+        if let Some(snapshot) = self.snapshot.take() {
+            self.data = snapshot;
+        }
+    }
+}
@@ -0,0 +1,611 @@
+/*
+    Admission rules for pending transactions
+*/
+
+// std library
+use std::collections::{HashMap, HashSet};
+
+// imports
+use super::{
+    block::Block,
+    error::MockchainError,
+    log::{Log, LogLevel},
+    state::ChainState,
+    store::ChainStore,
+    transaction::Transaction,
+};
+
+// pending transactions split into (admitted, held, dropped)
+pub type Selection = (Vec<Transaction>, Vec<Transaction>, Vec<Transaction>);
+
+/// Tracks the transaction hashes and sender nonces
+/// seen so far along the chain so replayed or out of
+/// order transactions can be rejected
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// seen: HashSet<String>      -> hashes of accepted transactions
+/// next: HashMap<String, u64> -> next expected nonce per sending address
+/// ```
+///
+/// # Derives
+/// ```
+/// Debug, Default
+/// ```
+#[derive(Debug, Default)]
+pub struct Sequence {
+    seen: HashSet<String>,
+    next: HashMap<String, u64>,
+}
+
+impl Sequence {
+    /// Builds the sequence of every transaction in a chain
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// blocks: &[Block] -> blocks in the chain
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Sequence
+    /// ```
+    pub fn from_blocks(blocks: &[Block]) -> Sequence {
+        let mut sequence = Sequence::default();
+        for t in blocks.iter().flat_map(|b| b.transactions.iter()) {
+            sequence.accept(t);
+        }
+        sequence
+    }
+
+    /// Checks a transaction has not been seen and carries
+    /// the next nonce of its sender. Coinbases are not
+    /// sequenced and only need a unique hash
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// transaction: &Transaction -> transaction to check
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    pub fn allows(&self, transaction: &Transaction) -> bool {
+        if self.seen.contains(&transaction.hash) {
+            return false;
+        }
+        transaction.is_coinbase()
+            || transaction.nonce == *self.next.get(&transaction.from_address).unwrap_or(&0)
+    }
+
+    /// Accepts a transaction if the sequence allows it
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// transaction: &Transaction -> transaction to accept
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    pub fn accept(&mut self, transaction: &Transaction) -> bool {
+        if !self.allows(transaction) {
+            return false;
+        }
+        if !transaction.is_coinbase() {
+            self.next
+                .insert(transaction.from_address.clone(), transaction.nonce + 1);
+        }
+        self.seen.insert(transaction.hash.clone());
+        true
+    }
+}
+
+/// Pool of pending transactions waiting to be mined.
+/// Transactions are admitted in the order they were
+/// submitted against the balances replayed from the
+/// chain, so the pending transfers of a sender can
+/// never add up to more than they can spend in the
+/// next block
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// sequence: Sequence -> hashes and nonces seen along the chain
+/// state: ChainState  -> balances per address after admitted transactions
+/// height: u64        -> height of the next block
+/// maturity: u64      -> blocks before a coinbase can be spent
+/// ```
+///
+/// # Derives
+/// ```
+/// Debug
+/// ```
+#[derive(Debug)]
+pub struct Mempool {
+    sequence: Sequence,
+    state: ChainState,
+    height: u64,
+    maturity: u64,
+}
+
+impl Mempool {
+    /// New Mempool starting from the confirmed state of
+    /// the chain
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// store: &dyn ChainStore -> storage backend holding the chain
+    /// maturity: u64          -> blocks before a coinbase can be spent
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Mempool, MockchainError>
+    /// ```
+    pub fn new(store: &dyn ChainStore, maturity: u64) -> Result<Mempool, MockchainError> {
+        let blocks = store.blocks()?;
+        Ok(Mempool {
+            sequence: Sequence::from_blocks(&blocks),
+            state: ChainState::replay(&blocks),
+            height: blocks.len() as u64,
+            maturity,
+        })
+    }
+
+    /// Gets the balance of an address the next block
+    /// can spend after the transactions admitted so far
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// address: &str -> address to lookup
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// i32
+    /// ```
+    pub fn balance(&self, address: &str) -> i32 {
+        self.state.spendable(address, self.height, self.maturity)
+    }
+
+    /// Checks a transaction has a positive amount, no
+    /// negative fee and a sender that can cover both.
+    /// Coinbases are only created by mining so are never
+    /// admitted
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// transaction: &Transaction -> transaction to check
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    fn covers(&self, transaction: &Transaction) -> bool {
+        !transaction.is_coinbase()
+            && transaction.amount > 0
+            && transaction.fee >= 0
+            && transaction
+                .cost()
+                .is_ok_and(|cost| self.balance(&transaction.from_address) >= cost)
+    }
+
+    /// Checks a transaction would be covered once every
+    /// coinbase of its sender has matured, so it can wait
+    /// for the next blocks rather than be dropped
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// transaction: &Transaction -> transaction to check
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    fn matures(&self, transaction: &Transaction) -> bool {
+        self.sequence.allows(transaction)
+            && !transaction.is_coinbase()
+            && transaction.amount > 0
+            && transaction.fee >= 0
+            && transaction
+                .cost()
+                .is_ok_and(|cost| self.state.balance(&transaction.from_address) >= cost)
+    }
+
+    /// Checks a transaction could be admitted next
+    /// without logging or admitting it
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// transaction: &Transaction -> transaction to check
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    pub fn allows(&self, transaction: &Transaction) -> bool {
+        self.sequence.allows(transaction) && self.covers(transaction)
+    }
+
+    /// Admits a transaction if it continues the nonce
+    /// sequence of its sender and the sender can cover
+    /// the amount and fee. Rejected transactions leave
+    /// the pool unchanged
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// transaction: &Transaction -> transaction to admit
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    pub fn admit(&mut self, transaction: &Transaction) -> bool {
+        if !self.allows(transaction) {
+            return false;
+        }
+        self.sequence.accept(transaction);
+        self.state.apply(transaction)
+    }
+
+    /// Logs why a transaction the pool rejected is being
+    /// dropped
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// transaction: &Transaction -> transaction being dropped
+    /// ```
+    ///
+    /// # Returns
+    /// Nothing
+    fn log_drop(&self, transaction: &Transaction) {
+        if !self.sequence.allows(transaction) {
+            Log::new(
+                LogLevel::WARNING,
+                38,
+                Some(vec![
+                    transaction.hash.clone(),
+                    transaction.nonce.to_string(),
+                    transaction.from_address.clone(),
+                ]),
+            );
+        } else {
+            Log::new(
+                LogLevel::WARNING,
+                41,
+                Some(vec![
+                    transaction.hash.clone(),
+                    transaction.from_address.clone(),
+                    format!("{} + {}", transaction.amount, transaction.fee),
+                ]),
+            );
+        }
+    }
+
+    /// Splits the pending transactions into the ones
+    /// that can be mined, the ones held until a coinbase
+    /// they spend matures and the ones that can never be
+    /// mined. Once a transaction of a sender is held the
+    /// ones it sends after it are held too, keeping its
+    /// nonce sequence intact
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// store: &dyn ChainStore -> storage backend holding the chain
+    /// maturity: u64          -> blocks before a coinbase can be spent
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Selection, MockchainError> -> (admitted, held, dropped)
+    /// ```
+    pub fn select(store: &dyn ChainStore, maturity: u64) -> Result<Selection, MockchainError> {
+        Mempool::sort(store, maturity, false)
+    }
+
+    /// Sorts the pending transactions as select does,
+    /// logging the ones dropped when they are about to
+    /// be discarded
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// store: &dyn ChainStore -> storage backend holding the chain
+    /// maturity: u64          -> blocks before a coinbase can be spent
+    /// log_drops: bool        -> log why each dropped transaction is dropped
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Selection, MockchainError> -> (admitted, held, dropped)
+    /// ```
+    fn sort(
+        store: &dyn ChainStore,
+        maturity: u64,
+        log_drops: bool,
+    ) -> Result<Selection, MockchainError> {
+        let mut mempool = Mempool::new(store, maturity)?;
+        let mut holding = HashSet::new();
+        let (mut admitted, mut held, mut dropped) = (Vec::new(), Vec::new(), Vec::new());
+        for transaction in store.transactions()? {
+            if holding.contains(&transaction.from_address) {
+                held.push(transaction);
+            } else if mempool.admit(&transaction) {
+                admitted.push(transaction);
+            } else if mempool.matures(&transaction) {
+                holding.insert(transaction.from_address.clone());
+                held.push(transaction);
+            } else {
+                if log_drops {
+                    mempool.log_drop(&transaction);
+                }
+                dropped.push(transaction);
+            }
+        }
+        Ok((admitted, held, dropped))
+    }
+
+    /// Picks the pending transactions for the next block.
+    /// The transaction paying the highest fee rate that
+    /// fits in the space left and can go next in its
+    /// senders nonce sequence is taken until none is
+    /// left. Transactions that can never be mined are
+    /// dropped, the rest keep waiting, with the ones held
+    /// until a coinbase matures placed last
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// store: &dyn ChainStore -> storage backend holding the chain
+    /// max_block_size: usize  -> bytes of transactions the block can hold, the coinbase aside
+    /// maturity: u64          -> blocks before a coinbase can be spent
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(Vec<Transaction>, Vec<Transaction>), MockchainError> -> (block, waiting)
+    /// ```
+    pub fn select_for_block(
+        store: &dyn ChainStore,
+        max_block_size: usize,
+        maturity: u64,
+    ) -> Result<(Vec<Transaction>, Vec<Transaction>), MockchainError> {
+        // only transactions dropped here are discarded for good, so
+        // only they are logged
+        let (mut waiting, held, _) = Mempool::sort(store, maturity, true)?;
+
+        let mut mempool = Mempool::new(store, maturity)?;
+        let mut block = Vec::with_capacity(waiting.len());
+        let mut space = max_block_size;
+        loop {
+            // earlier submissions win ties
+            let best = waiting
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| t.size() <= space && mempool.allows(t))
+                .max_by(|(i, a), (j, b)| a.cmp_fee_rate(b).then(j.cmp(i)))
+                .map(|(i, _)| i);
+            match best {
+                Some(i) => {
+                    let transaction = waiting.remove(i);
+                    space -= transaction.size();
+                    mempool.admit(&transaction);
+                    block.push(transaction);
+                }
+                None => break,
+            }
+        }
+        waiting.extend(held);
+        Ok((block, waiting))
+    }
+
+    /// Gets the pending transactions ordered by fee rate,
+    /// highest first, as a miner would consider them
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// store: &dyn ChainStore -> storage backend holding the chain
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<Transaction>, MockchainError>
+    /// ```
+    pub fn by_fee_rate(store: &dyn ChainStore) -> Result<Vec<Transaction>, MockchainError> {
+        let mut pending = store.transactions()?;
+        // a stable sort keeps submission order between equal rates
+        pending.sort_by(|a, b| b.cmp_fee_rate(a));
+        Ok(pending)
+    }
+
+    /// Gets the balance an address can still spend in
+    /// the next block once its pending outgoing transfers
+    /// are paid. Pending incoming transfers and coinbases
+    /// that have not matured are not counted
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// store: &dyn ChainStore -> storage backend holding the chain
+    /// address: &str          -> address to lookup
+    /// maturity: u64          -> blocks before a coinbase can be spent
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<i32, MockchainError> -> InvalidFee or InvalidAmount if the pending costs overflow
+    /// ```
+    pub fn spendable_balance(
+        store: &dyn ChainStore,
+        address: &str,
+        maturity: u64,
+    ) -> Result<i32, MockchainError> {
+        let confirmed = Mempool::new(store, maturity)?.balance(address);
+        let (admitted, _, _) = Mempool::select(store, maturity)?;
+        let outgoing = admitted
+            .iter()
+            .filter(|t| t.from_address == address)
+            .try_fold(0, |outgoing: i32, t| {
+                t.cost()?
+                    .checked_add(outgoing)
+                    .ok_or(MockchainError::InvalidAmount(t.amount))
+            })?;
+        Ok(confirmed - outgoing)
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_mempool {
+    use super::*;
+
+    use crate::mods::{
+        config::ChainConfig,
+        genesis::GenesisSpec,
+        helpers::{create_transaction, create_wallet, mine_block, verify_chain},
+        keystore::Keystore,
+        memory::MemoryStore,
+        wallet::Wallet,
+    };
+
+    #[test]
+    fn test_pending_transfers_cannot_overspend() {
+        let mut store = MemoryStore::new();
+        let mut keystore = Keystore::new(true);
+        store.init(false).unwrap();
+        Block::add_genesis_block(&mut store, &GenesisSpec::default()).unwrap();
+        let alice = create_wallet(&mut store, "alice", &mut keystore).unwrap();
+        let bob = create_wallet(&mut store, "bob", &mut keystore).unwrap();
+        let config = ChainConfig::default();
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+        mine_block(&mut store, "bob", &config, &mut keystore).unwrap();
+        let maturity = config.coinbase_maturity;
+
+        // 50 confirmed tokens but 60 queued to send
+        create_transaction(&mut store, "alice", "bob", 30, 0, &mut keystore).unwrap();
+        let overspend =
+            create_transaction(&mut store, "alice", "bob", 30, 0, &mut keystore).unwrap();
+        assert_eq!(
+            20,
+            Mempool::spendable_balance(&store, &alice.address, maturity).unwrap()
+        );
+        // the coinbase of the second block has not matured yet
+        assert_eq!(
+            0,
+            Mempool::spendable_balance(&store, &bob.address, maturity).unwrap()
+        );
+
+        let (admitted, held, dropped) = Mempool::select(&store, maturity).unwrap();
+        assert_eq!(1, admitted.len());
+        assert!(held.is_empty());
+        assert_eq!(1, dropped.len());
+        assert_eq!(overspend.hash, dropped[0].hash);
+
+        mine_block(&mut store, "bob", &config, &mut keystore).unwrap();
+        assert_eq!(20, Wallet::get_balance(&store, "alice").unwrap());
+        // bob is paid a coinbase for each block mined as well
+        assert_eq!(130, Wallet::get_balance(&store, "bob").unwrap());
+    }
+
+    #[test]
+    fn test_blocks_fill_by_fee_rate() {
+        let mut store = MemoryStore::new();
+        let mut keystore = Keystore::new(true);
+        store.init(false).unwrap();
+        Block::add_genesis_block(&mut store, &GenesisSpec::default()).unwrap();
+        create_wallet(&mut store, "alice", &mut keystore).unwrap();
+        let bob = create_wallet(&mut store, "bob", &mut keystore).unwrap();
+        let config = ChainConfig::default();
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+        mine_block(&mut store, "bob", &config, &mut keystore).unwrap();
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+
+        let cheap = create_transaction(&mut store, "alice", "bob", 10, 1, &mut keystore).unwrap();
+        let dear = create_transaction(&mut store, "bob", "alice", 10, 9, &mut keystore).unwrap();
+        assert_eq!(dear.hash, Mempool::by_fee_rate(&store).unwrap()[0].hash);
+
+        // room for a single transfer besides the coinbase
+        let config = ChainConfig {
+            max_block_size: cheap.size().max(dear.size()),
+            ..ChainConfig::default()
+        };
+        let maturity = config.coinbase_maturity;
+        let (block, waiting) =
+            Mempool::select_for_block(&store, config.max_block_size, maturity).unwrap();
+        assert_eq!(1, block.len());
+        assert_eq!(dear.hash, block[0].hash);
+        assert_eq!(cheap.hash, waiting[0].hash);
+
+        // the cheaper transfer waits and the fee joins the coinbase
+        let mined = mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+        assert!(mined.transactions[0].is_coinbase());
+        assert_eq!(59, mined.transactions[0].amount);
+        assert_eq!(dear.hash, mined.transactions[1].hash);
+        let pending = store.transactions().unwrap();
+        assert_eq!(1, pending.len());
+        assert_eq!(cheap.hash, pending[0].hash);
+        // bob paid 10 and a fee of 9 out of 50
+        assert_eq!(
+            31,
+            Mempool::spendable_balance(&store, &bob.address, maturity).unwrap()
+        );
+
+        let mined = mine_block(&mut store, "bob", &config, &mut keystore).unwrap();
+        assert_eq!(51, mined.transactions[0].amount);
+        assert!(verify_chain(&store, &config).unwrap().valid);
+    }
+}
@@ -77,7 +77,7 @@ pub fn display_msg(level: Message) {
 /// String
 /// ```
 pub fn replace(message: String, vec: Vec<String>) -> String {
-    let mut result = String::from(message);
+    let mut result = message;
     for value in &vec {
         if let Some(pos) = result.find("{}") {
             result.replace_range(pos..pos + 2, value);
@@ -1,11 +1,27 @@
+pub mod address;
 pub mod base;
 pub mod block;
+pub mod canonical;
+pub mod chain;
+pub mod cli;
+pub mod config;
 pub mod crypto;
+pub mod error;
 pub mod file;
+pub mod genesis;
+pub mod hd;
 pub mod helpers;
+pub mod keystore;
 pub mod log;
+pub mod memory;
+pub mod mempool;
 pub mod messaging;
+pub mod profile;
+pub mod render;
 pub mod repl;
 pub mod signing_data;
+pub mod state;
+pub mod store;
 pub mod transaction;
+pub mod verification;
 pub mod wallet;
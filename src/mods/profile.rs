@@ -0,0 +1,449 @@
+/*
+    Named chain profiles kept under one data root
+*/
+
+// std library
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 3rd party crates
+use dirs::home_dir;
+
+// imports
+use super::{
+    chain::Mockchain,
+    config::ChainConfig,
+    error::MockchainError,
+    file::{FileOps, LOCK_FILE},
+    genesis::GenesisSpec,
+    log::{Log, LogLevel},
+    store::ChainStore,
+};
+
+// profile kept directly under the data root
+pub const DEFAULT_PROFILE: &str = "default";
+
+// environment variable overriding the data root
+pub const HOME_VAR: &str = "MOCKCHAIN_HOME";
+
+/// Chain profiles kept under a data root. The default
+/// profile uses the data/, log/, config.json and
+/// genesis.json entries of the root itself and every
+/// other profile gets the same layout under
+/// chains/<name>/
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// root: PathBuf -> directory holding every profile
+/// ```
+///
+/// # Derives
+/// ```
+/// Debug, Clone
+/// ```
+#[derive(Debug, Clone)]
+pub struct Profiles {
+    root: PathBuf,
+}
+
+impl Profiles {
+    /// New instance of Profiles
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// root: &Path -> directory holding every profile
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Profiles
+    /// ```
+    pub fn new(root: &Path) -> Profiles {
+        Profiles {
+            root: root.to_path_buf(),
+        }
+    }
+
+    /// Works out the data root. An explicit directory
+    /// wins over MOCKCHAIN_HOME, which wins over the
+    /// .mockchain directory under $HOME
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// data_dir: Option<&str> -> directory given with --data-dir
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Profiles, MockchainError>
+    /// ```
+    pub fn locate(data_dir: Option<&str>) -> Result<Profiles, MockchainError> {
+        if let Some(dir) = data_dir {
+            return Ok(Profiles::new(Path::new(dir)));
+        }
+        if let Ok(dir) = env::var(HOME_VAR) {
+            if !dir.is_empty() {
+                return Ok(Profiles::new(Path::new(&dir)));
+            }
+        }
+        match home_dir() {
+            Some(home) => Ok(Profiles::new(&home.join(".mockchain"))),
+            None => Err(MockchainError::Io(
+                "Home directory not found to write data files".to_string(),
+            )),
+        }
+    }
+
+    /// Gets the directory holding a profile
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str -> name of the profile
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// PathBuf
+    /// ```
+    pub fn path(&self, name: &str) -> PathBuf {
+        if name == DEFAULT_PROFILE {
+            self.root.clone()
+        } else {
+            self.root.join("chains").join(name)
+        }
+    }
+
+    /// Gets the JSON data directory of a profile
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str -> name of the profile
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// PathBuf
+    /// ```
+    pub fn data_path(&self, name: &str) -> PathBuf {
+        self.path(name).join("data")
+    }
+
+    /// Gets the log directory of a profile
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str -> name of the profile
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// PathBuf
+    /// ```
+    pub fn log_path(&self, name: &str) -> PathBuf {
+        self.path(name).join("log")
+    }
+
+    /// Gets the config file of a profile
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str -> name of the profile
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// PathBuf
+    /// ```
+    pub fn config_path(&self, name: &str) -> PathBuf {
+        self.path(name).join("config.json")
+    }
+
+    /// Gets the genesis spec file of a profile
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str -> name of the profile
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// PathBuf
+    /// ```
+    pub fn genesis_path(&self, name: &str) -> PathBuf {
+        self.path(name).join("genesis.json")
+    }
+
+    /// Checks a profile has been created
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str -> name of the profile
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    pub fn exists(&self, name: &str) -> bool {
+        FileOps::new(&self.data_path(name)).exists()
+    }
+
+    /// Lists every profile, default first
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<String>, MockchainError>
+    /// ```
+    pub fn list(&self) -> Result<Vec<String>, MockchainError> {
+        let mut names = Vec::new();
+        let chains = self.root.join("chains");
+        if chains.exists() {
+            let entries = match fs::read_dir(&chains) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    return Err(MockchainError::Io(format!(
+                        "Error reading profiles in {:?}: {}",
+                        chains, e
+                    )))
+                }
+            };
+            for entry in entries.flatten() {
+                if entry.path().is_dir() {
+                    names.push(entry.file_name().to_string_lossy().to_string());
+                }
+            }
+        }
+        names.sort();
+        names.insert(0, DEFAULT_PROFILE.to_string());
+        Ok(names)
+    }
+
+    /// Opens the chain of a profile, creating it first
+    /// if needed, and directs log entries to its log.
+    /// The chain is opened read-only if another process
+    /// holds the lock on its data
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str -> name of the profile
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Mockchain, MockchainError>
+    /// ```
+    pub fn open(&self, name: &str) -> Result<Mockchain, MockchainError> {
+        validate_name(name)?;
+        let mut config = ChainConfig::load(&self.config_path(name))?;
+        config.genesis = GenesisSpec::load(&self.genesis_path(name))?;
+        Log::init(&self.log_path(name))?;
+        let data_path = self.data_path(name);
+        let store = match FileOps::locked(&data_path) {
+            Ok(store) => store,
+            Err(MockchainError::Locked(pid)) => {
+                Log::new(LogLevel::WARNING, 47, Some(vec![pid.clone()]));
+                FileOps::read_only(&data_path, &pid)
+            }
+            Err(e) => return Err(e),
+        };
+        if !store.exists() {
+            Log::new(LogLevel::INFO, 1, None);
+        }
+        Mockchain::with_config(Box::new(store), config)
+    }
+
+    /// Copies the data and config of a profile into a
+    /// new profile. The new profile starts a fresh log
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// from: &str -> name of the profile to copy
+    /// to: &str   -> name of the new profile
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    pub fn clone_profile(&self, from: &str, to: &str) -> Result<(), MockchainError> {
+        validate_name(to)?;
+        if !self.exists(from) {
+            return Err(MockchainError::ProfileNotFound(from.to_string()));
+        }
+        if to == DEFAULT_PROFILE || self.path(to).exists() {
+            return Err(MockchainError::ProfileExists(to.to_string()));
+        }
+        copy_dir(&self.data_path(from), &self.data_path(to))?;
+        // the genesis spec goes with the chain it describes
+        for (from_file, to_file) in [
+            (self.config_path(from), self.config_path(to)),
+            (self.genesis_path(from), self.genesis_path(to)),
+        ] {
+            if from_file.exists() {
+                if let Err(e) = fs::copy(&from_file, to_file) {
+                    return Err(MockchainError::Io(format!(
+                        "Error copying {:?}: {}",
+                        from_file, e
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes a profile and everything in it. The
+    /// default profile cannot be deleted
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// name: &str -> name of the profile
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    pub fn delete(&self, name: &str) -> Result<(), MockchainError> {
+        validate_name(name)?;
+        if name == DEFAULT_PROFILE {
+            return Err(MockchainError::InvalidProfile(
+                "The default chain cannot be deleted".to_string(),
+            ));
+        }
+        let path = self.path(name);
+        if !path.exists() {
+            return Err(MockchainError::ProfileNotFound(name.to_string()));
+        }
+        // refuse to delete a chain another process has open
+        drop(FileOps::locked(&self.data_path(name))?);
+        match fs::remove_dir_all(&path) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(MockchainError::Io(format!(
+                "Error deleting {:?}: {}",
+                path, e
+            ))),
+        }
+    }
+}
+
+/// Checks a profile name is safe to use as a
+/// directory name
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// name: &str -> name of the profile
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<(), MockchainError>
+/// ```
+pub fn validate_name(name: &str) -> Result<(), MockchainError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(MockchainError::InvalidProfile(format!(
+            "'{}' is not a valid chain name; use letters, numbers, '-' and '_'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Copies the files of a directory, other than its
+/// lock file, into a new directory
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// from: &Path -> directory to copy
+/// to: &Path   -> directory to create
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<(), MockchainError>
+/// ```
+fn copy_dir(from: &Path, to: &Path) -> Result<(), MockchainError> {
+    let io_err = |e: std::io::Error| MockchainError::Io(format!("Error copying {:?}: {}", from, e));
+    fs::create_dir_all(to).map_err(io_err)?;
+    for entry in fs::read_dir(from).map_err(io_err)?.flatten() {
+        let path = entry.path();
+        if path.is_file() && entry.file_name() != LOCK_FILE {
+            fs::copy(&path, to.join(entry.file_name())).map_err(io_err)?;
+        }
+    }
+    Ok(())
+}
+
+// Testing
+#[cfg(test)]
+mod test_profile {
+    use super::*;
+
+    #[test]
+    fn test_profile_layout() {
+        let profiles = Profiles::new(Path::new("/tmp/mockchain"));
+        assert_eq!(
+            Path::new("/tmp/mockchain/data"),
+            profiles.data_path(DEFAULT_PROFILE)
+        );
+        assert_eq!(
+            Path::new("/tmp/mockchain/chains/testnet-a/log"),
+            profiles.log_path("testnet-a")
+        );
+        assert!(validate_name("testnet-a").is_ok());
+        assert!(validate_name("../escape").is_err());
+        assert!(validate_name("").is_err());
+    }
+}
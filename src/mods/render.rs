@@ -0,0 +1,550 @@
+/*
+    Output formats shared by the menu and subcommands
+*/
+
+// std library
+use std::fmt;
+use std::str::FromStr;
+
+// 3rd party crates
+use serde::Serialize;
+use serde_json::{to_string, to_string_pretty, to_value, Value};
+
+// imports
+use super::{
+    block::Block, chain::Mockchain, crypto::KeyPair, error::MockchainError, signing_data::Signing,
+    state::SupplyPoint, store::DataFile, transaction::Transaction,
+    verification::VerificationReport, wallet::Wallet,
+};
+
+// characters of a hash or key shown in table cells
+const PREFIX_LEN: usize = 16;
+
+/// Formats data can be written out in
+///
+/// # Visibility
+/// public
+///
+/// # Variants
+/// ```
+/// Json   -> compact JSON for other tools to consume
+/// Table  -> aligned columns with hashes and keys shortened
+/// Pretty -> indented JSON
+/// ```
+///
+/// # Derives
+/// ```
+/// Debug, Clone, Copy, PartialEq, Eq, Default
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    Json,
+    Table,
+    #[default]
+    Pretty,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<OutputFormat, String> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "table" => Ok(OutputFormat::Table),
+            "pretty" => Ok(OutputFormat::Pretty),
+            _ => Err(format!(
+                "Unknown output format '{}'; expected json, table or pretty",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Table => write!(f, "table"),
+            OutputFormat::Pretty => write!(f, "pretty"),
+        }
+    }
+}
+
+/// Renders one of the chains data sets
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// chain: &Mockchain      -> chain to read from
+/// file: DataFile         -> data set to render
+/// format: OutputFormat   -> format to render in
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<String, MockchainError>
+/// ```
+pub fn render_data(
+    chain: &Mockchain,
+    file: DataFile,
+    format: OutputFormat,
+) -> Result<String, MockchainError> {
+    match file {
+        DataFile::Blockchain => render_blocks(&chain.blocks()?, format),
+        DataFile::Transactions => render_transactions(&chain.pending_transactions()?, format),
+        DataFile::Wallets => render_wallets(&chain.wallets()?, format),
+        DataFile::KeyPairs => render_keypairs(&chain.keypairs()?, format),
+        DataFile::SigningData => render_signing_data(&chain.signing_data()?, format),
+    }
+}
+
+/// Renders a list of blocks
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// blocks: &[Block]     -> blocks to render
+/// format: OutputFormat -> format to render in
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<String, MockchainError>
+/// ```
+pub fn render_blocks(blocks: &[Block], format: OutputFormat) -> Result<String, MockchainError> {
+    if format != OutputFormat::Table {
+        return render_object(&blocks, format);
+    }
+    let rows = blocks
+        .iter()
+        .map(|b| {
+            vec![
+                b.header.height.to_string(),
+                shorten(&b.hash),
+                b.header.nonce.to_string(),
+                b.header.difficulty.to_string(),
+                b.transactions.len().to_string(),
+                shorten(&b.header.merkle_root),
+            ]
+        })
+        .collect();
+    Ok(table(
+        &[
+            "HEIGHT",
+            "HASH",
+            "NONCE",
+            "DIFFICULTY",
+            "TXS",
+            "MERKLE ROOT",
+        ],
+        rows,
+    ))
+}
+
+/// Renders a list of transactions
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// transactions: &[Transaction] -> transactions to render
+/// format: OutputFormat         -> format to render in
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<String, MockchainError>
+/// ```
+pub fn render_transactions(
+    transactions: &[Transaction],
+    format: OutputFormat,
+) -> Result<String, MockchainError> {
+    if format != OutputFormat::Table {
+        return render_object(&transactions, format);
+    }
+    let rows = transactions
+        .iter()
+        .map(|t| {
+            vec![
+                shorten(&t.hash),
+                shorten(&t.from_address),
+                shorten(&t.to_address),
+                t.amount.to_string(),
+                t.fee.to_string(),
+                format!("{:.2}", t.fee_rate()),
+                t.nonce.to_string(),
+            ]
+        })
+        .collect();
+    Ok(table(
+        &["HASH", "FROM", "TO", "AMOUNT", "FEE", "FEE/KB", "NONCE"],
+        rows,
+    ))
+}
+
+/// Renders a list of wallets
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// wallets: &[Wallet]   -> wallets to render
+/// format: OutputFormat -> format to render in
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<String, MockchainError>
+/// ```
+pub fn render_wallets(wallets: &[Wallet], format: OutputFormat) -> Result<String, MockchainError> {
+    if format != OutputFormat::Table {
+        return render_object(&wallets, format);
+    }
+    let rows = wallets
+        .iter()
+        .map(|w| vec![w.name.clone(), shorten(&w.address), w.balance.to_string()])
+        .collect();
+    Ok(table(&["NAME", "ADDRESS", "BALANCE"], rows))
+}
+
+/// Renders a list of key pairs
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// keypairs: &[KeyPair] -> key pairs to render
+/// format: OutputFormat -> format to render in
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<String, MockchainError>
+/// ```
+pub fn render_keypairs(
+    keypairs: &[KeyPair],
+    format: OutputFormat,
+) -> Result<String, MockchainError> {
+    if format != OutputFormat::Table {
+        return render_object(&keypairs, format);
+    }
+    let rows = keypairs
+        .iter()
+        .map(|k| {
+            vec![
+                k.name.clone(),
+                shorten(&k.public_key),
+                match k.crypto {
+                    Some(_) => String::from("(encrypted)"),
+                    None => shorten(&k.private_key),
+                },
+            ]
+        })
+        .collect();
+    Ok(table(&["NAME", "PUBLIC KEY", "PRIVATE KEY"], rows))
+}
+
+/// Renders a list of signing records
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// signing_data: &[Signing] -> signing records to render
+/// format: OutputFormat     -> format to render in
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<String, MockchainError>
+/// ```
+pub fn render_signing_data(
+    signing_data: &[Signing],
+    format: OutputFormat,
+) -> Result<String, MockchainError> {
+    if format != OutputFormat::Table {
+        return render_object(&signing_data, format);
+    }
+    let rows = signing_data
+        .iter()
+        .map(|s| {
+            vec![
+                s.name.clone(),
+                shorten(&s.hash),
+                shorten(&s.public_key),
+                shorten(&s.signature),
+            ]
+        })
+        .collect();
+    Ok(table(&["NAME", "HASH", "PUBLIC KEY", "SIGNATURE"], rows))
+}
+
+/// Renders the circulating supply by block height
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// supply: &[SupplyPoint] -> supply at each height to render
+/// format: OutputFormat   -> format to render in
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<String, MockchainError>
+/// ```
+pub fn render_supply(
+    supply: &[SupplyPoint],
+    format: OutputFormat,
+) -> Result<String, MockchainError> {
+    if format != OutputFormat::Table {
+        return render_object(&supply, format);
+    }
+    let rows = supply
+        .iter()
+        .map(|p| {
+            vec![
+                p.height.to_string(),
+                p.subsidy.to_string(),
+                p.circulating.to_string(),
+            ]
+        })
+        .collect();
+    Ok(table(&["HEIGHT", "SUBSIDY", "CIRCULATING"], rows))
+}
+
+/// Renders the failures found verifying the chain.
+/// Tables list one failure per row, failures outside
+/// the blocks show a dash for the height and hash
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// report: &VerificationReport -> report to render
+/// format: OutputFormat        -> format to render in
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<String, MockchainError>
+/// ```
+pub fn render_verification(
+    report: &VerificationReport,
+    format: OutputFormat,
+) -> Result<String, MockchainError> {
+    if format != OutputFormat::Table {
+        return render_object(report, format);
+    }
+    let dash = || String::from("-");
+    let rows = report
+        .failures
+        .iter()
+        .map(|f| {
+            vec![
+                f.height.map(|h| h.to_string()).unwrap_or_else(dash),
+                f.block_hash.as_deref().map(shorten).unwrap_or_else(dash),
+                f.transaction_index
+                    .map(|i| i.to_string())
+                    .unwrap_or_else(dash),
+                f.kind.to_string(),
+                f.detail.clone(),
+            ]
+        })
+        .collect();
+    Ok(table(&["HEIGHT", "HASH", "TX", "KIND", "DETAIL"], rows))
+}
+
+/// Renders any serializable object. Tables list the
+/// top level fields of the object one per row
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// obj: &T              -> an object T that implements Serialize
+/// format: OutputFormat -> format to render in
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<String, MockchainError>
+/// ```
+pub fn render_object<T: Serialize>(
+    obj: &T,
+    format: OutputFormat,
+) -> Result<String, MockchainError> {
+    let rendered = match format {
+        OutputFormat::Json => to_string(obj),
+        OutputFormat::Pretty => to_string_pretty(obj),
+        OutputFormat::Table => {
+            return match to_value(obj) {
+                Ok(Value::Object(map)) => {
+                    let rows = map
+                        .iter()
+                        .map(|(key, value)| {
+                            let value = match value {
+                                Value::String(s) => s.clone(),
+                                other => other.to_string(),
+                            };
+                            vec![key.to_uppercase(), value]
+                        })
+                        .collect();
+                    Ok(table(&["FIELD", "VALUE"], rows))
+                }
+                Ok(other) => Ok(other.to_string()),
+                Err(e) => Err(MockchainError::Json(e.to_string())),
+            };
+        }
+    };
+    rendered.map_err(|e| MockchainError::Json(e.to_string()))
+}
+
+/// Shortens a hash or key to its first characters
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// value: &str -> value to shorten
+/// ```
+///
+/// # Returns
+/// ```
+/// String
+/// ```
+fn shorten(value: &str) -> String {
+    match value.char_indices().nth(PREFIX_LEN) {
+        Some((i, _)) => format!("{}...", &value[..i]),
+        None => value.to_string(),
+    }
+}
+
+/// Lays out rows of cells in left aligned columns
+///
+/// # Visibility
+/// private
+///
+/// # Args
+/// ```
+/// headers: &[&str]       -> column headers
+/// rows: Vec<Vec<String>> -> cells of each row
+/// ```
+///
+/// # Returns
+/// ```
+/// String
+/// ```
+fn table(headers: &[&str], rows: Vec<Vec<String>>) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in rows.iter() {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<String>| -> String {
+        cells
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| format!("{:<width$}", cell, width = widths[i]))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    let mut lines = vec![
+        line(headers.iter().map(|h| h.to_string()).collect()),
+        line(widths.iter().map(|w| "-".repeat(*w)).collect()),
+    ];
+    lines.extend(rows.into_iter().map(line));
+    lines.join("\n")
+}
+
+// Testing
+#[cfg(test)]
+mod test_render {
+    use super::*;
+
+    use crate::mods::verification::{FailureKind, VerificationFailure};
+
+    #[test]
+    fn test_wallet_table() {
+        let wallets = [Wallet {
+            name: String::from("alice"),
+            address: "a".repeat(130),
+            balance: 50,
+        }];
+        let expected = "\
+NAME   ADDRESS              BALANCE
+-----  -------------------  -------
+alice  aaaaaaaaaaaaaaaa...  50";
+        assert_eq!(
+            expected,
+            render_wallets(&wallets, OutputFormat::Table).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_json_round_trips() {
+        let wallets = [Wallet {
+            name: String::from("alice"),
+            address: "a".repeat(130),
+            balance: 50,
+        }];
+        let json = render_wallets(&wallets, OutputFormat::Json).unwrap();
+        let parsed: Vec<Wallet> = serde_json::from_str(&json).unwrap();
+        assert_eq!(wallets[0].address, parsed[0].address);
+        assert!(!json.contains('\n'));
+    }
+
+    #[test]
+    fn test_verification_table() {
+        let report = VerificationReport {
+            valid: false,
+            blocks: 3,
+            failures: vec![
+                VerificationFailure {
+                    kind: FailureKind::BadSignature,
+                    height: Some(2),
+                    block_hash: Some("b".repeat(64)),
+                    transaction_index: Some(1),
+                    detail: String::from("signature does not verify"),
+                },
+                VerificationFailure {
+                    kind: FailureKind::BalanceMismatch,
+                    height: None,
+                    block_hash: None,
+                    transaction_index: None,
+                    detail: String::from("balance of 'bob' differs"),
+                },
+            ],
+        };
+        let expected = "\
+HEIGHT  HASH                 TX  KIND              DETAIL
+------  -------------------  --  ----------------  -------------------------
+2       bbbbbbbbbbbbbbbb...  1   bad_signature     signature does not verify
+-       -                    -   balance_mismatch  balance of 'bob' differs";
+        assert_eq!(
+            expected,
+            render_verification(&report, OutputFormat::Table).unwrap()
+        );
+
+        // JSON exports round trip with the kinds in snake case
+        let json = render_verification(&report, OutputFormat::Json).unwrap();
+        assert!(json.contains("\"kind\":\"bad_signature\""));
+        let parsed: VerificationReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(report, parsed);
+    }
+}
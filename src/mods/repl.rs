@@ -1,5 +1,6 @@
 // std
 use std::fmt::{Debug, Display};
+use std::io::{self, Write};
 use std::str::FromStr;
 
 // 3rd party crates
@@ -72,7 +73,18 @@ impl Repl {
         println!("8. Display signatures");
         println!("9. Re-initialise blockchain");
         println!("10. Verify blockchain");
-        println!("11. Exit\n");
+        println!("11. Exit");
+        println!("12. Prove a transaction is in a block");
+        println!("13. List chain profiles");
+        println!("14. Switch chain profile");
+        println!("15. Clone chain profile");
+        println!("16. Delete chain profile");
+        println!("17. Generate a seed phrase");
+        println!("18. Add account from seed");
+        println!("19. Display pending transactions sorted by fee");
+        println!("20. Display supply by block height");
+        println!("21. Display a block by height");
+        println!("22. Export the verification report as JSON\n");
     }
 
     /// Gets user input from the console and performs
@@ -97,15 +109,27 @@ impl Repl {
     where
         <T as FromStr>::Err: Debug,
     {
-        let i: Option<T> = match try_read!() {
-            Ok(val) => Some(val),
-            Err(_) => None,
-        };
+        let i: Result<T, _> = try_read!();
+        i.ok()
+    }
 
-        if i.is_none() {
-            None
-        } else {
-            i
+    /// Reads a whole line from the console, for input
+    /// that may contain spaces such as a seed phrase.
+    /// Returns the trimmed line, which may be empty
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Option<String> -> None when the console is closed
+    /// ```
+    pub fn get_line() -> Option<String> {
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        match io::stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim().to_string()),
         }
     }
 }
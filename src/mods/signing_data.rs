@@ -1,5 +1,5 @@
 // 3rd party crates
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Define a Signing object
 ///
@@ -10,18 +10,18 @@ use serde::Serialize;
 /// ```
 /// name: String        -> name of signing account
 /// hash: String        -> transaction hash
-/// signing_key: String -> signing key (encoded as hex byte string)
+/// public_key: String  -> signers public key (encoded as hex SEC1 bytes)
 /// signature: String   -> signature (encoded as hex byte string)
 /// ```
 ///
 /// # Derives
 /// ```
-/// serde::Serialize, Debug
+/// serde::Serialize, serde::Deserialize, Debug, Clone
 /// ```
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Signing {
     pub name: String,
     pub hash: String,
-    pub signing_key: String,
+    pub public_key: String,
     pub signature: String,
 }
@@ -0,0 +1,406 @@
+/*
+    Account state derived by replaying the chain
+*/
+
+// std library
+use std::collections::HashMap;
+
+// 3rd party crates
+use serde::{Deserialize, Serialize};
+
+// imports
+use super::{
+    block::Block,
+    config::MonetaryPolicy,
+    error::MockchainError,
+    store::{to_records, ChainStore, DataFile},
+    transaction::Transaction,
+    wallet::Wallet,
+};
+
+/// Difference between the balance stored for a wallet
+/// and the balance derived from the chain
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// name: String    -> name on the wallet
+/// address: String -> address of the wallet
+/// stored: i32     -> balance found in wallets.json
+/// derived: i32    -> balance given by replaying the chain
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BalanceMismatch {
+    pub name: String,
+    pub address: String,
+    pub stored: i32,
+    pub derived: i32,
+}
+
+/// Tokens issued by the chain at a block height
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// height: u64       -> height of the Block
+/// subsidy: i32      -> subsidy the monetary policy pays for the Block
+/// circulating: i64  -> genesis allocations plus subsidies paid up to and including the Block
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SupplyPoint {
+    pub height: u64,
+    pub subsidy: i32,
+    pub circulating: i64,
+}
+
+/// Balances of every account found by replaying the
+/// transactions of the chain from genesis. This is
+/// the source of truth for balances, the values in
+/// wallets.json are only a cached copy
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// balances: HashMap<String, i32>            -> balance per address
+/// coinbases: HashMap<String, Vec<(u64, i32)>> -> height and amount of each coinbase per address
+/// ```
+///
+/// # Derives
+/// ```
+/// Debug, Default, Clone
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct ChainState {
+    balances: HashMap<String, i32>,
+    coinbases: HashMap<String, Vec<(u64, i32)>>,
+}
+
+impl ChainState {
+    /// Replays every transaction in a list of blocks
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// blocks: &[Block] -> blocks in the chain, genesis first
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// ChainState
+    /// ```
+    pub fn replay(blocks: &[Block]) -> ChainState {
+        let mut state = ChainState::default();
+        for t in blocks.iter().flat_map(|b| b.transactions.iter()) {
+            state.apply(t);
+        }
+        state
+    }
+
+    /// Replays the chain held in a storage backend
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// store: &dyn ChainStore -> storage backend holding the chain
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<ChainState, MockchainError>
+    /// ```
+    pub fn from_store(store: &dyn ChainStore) -> Result<ChainState, MockchainError> {
+        Ok(ChainState::replay(&store.blocks()?))
+    }
+
+    /// Replays the coinbases in a list of blocks to find
+    /// the circulating supply at every height. Fees only
+    /// move tokens between accounts so just the subsidy
+    /// of each coinbase adds to the supply, on top of the
+    /// allocations of the genesis block
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// blocks: &[Block]         -> blocks in the chain, genesis first
+    /// policy: &MonetaryPolicy  -> policy the subsidies are paid under
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Vec<SupplyPoint>
+    /// ```
+    pub fn supply(blocks: &[Block], policy: &MonetaryPolicy) -> Vec<SupplyPoint> {
+        // added up as an i64 like the issued supply, so large
+        // allocations on top of the subsidies cannot overflow
+        let mut circulating: i64 = 0;
+        blocks
+            .iter()
+            .enumerate()
+            .map(|(height, block)| {
+                circulating += block
+                    .transactions
+                    .iter()
+                    .filter(|t| t.is_coinbase())
+                    .map(|t| policy.subsidy(t.nonce) as i64)
+                    .sum::<i64>();
+                circulating += block
+                    .transactions
+                    .iter()
+                    .filter(|t| t.is_allocation())
+                    .map(|t| t.amount as i64)
+                    .sum::<i64>();
+                SupplyPoint {
+                    height: height as u64,
+                    subsidy: policy.subsidy(height as u64),
+                    circulating,
+                }
+            })
+            .collect()
+    }
+
+    /// Applies a transaction to the balances. Coinbases
+    /// credit the miner and are recorded until they
+    /// mature, genesis allocations credit the recipient
+    /// and every other transaction moves the amount from
+    /// sender to recipient and takes the fee from the
+    /// sender, to be paid out in the coinbase. A
+    /// transaction the sender cannot cover is not applied
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// transaction: &Transaction -> transaction to apply
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// bool -> false if the sender could not cover the amount and fee
+    /// ```
+    pub fn apply(&mut self, transaction: &Transaction) -> bool {
+        if transaction.is_coinbase() {
+            self.coinbases
+                .entry(transaction.to_address.clone())
+                .or_default()
+                .push((transaction.nonce, transaction.amount));
+        } else if !transaction.is_allocation() {
+            let from = self
+                .balances
+                .entry(transaction.from_address.clone())
+                .or_insert(0);
+            // a cost too large for an i32 cannot be covered either
+            match transaction.cost() {
+                Ok(cost) if *from >= cost => *from -= cost,
+                _ => return false,
+            }
+        }
+        let to = self
+            .balances
+            .entry(transaction.to_address.clone())
+            .or_insert(0);
+        *to = to.saturating_add(transaction.amount);
+        true
+    }
+
+    /// Gets the balance of an address
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// address: &str -> address to lookup
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// i32
+    /// ```
+    pub fn balance(&self, address: &str) -> i32 {
+        *self.balances.get(address).unwrap_or(&0)
+    }
+
+    /// Gets the balance of an address that a transaction
+    /// in the Block at a height can spend. A coinbase
+    /// only becomes spendable in the Block the maturity
+    /// number of blocks after the one that mined it
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// address: &str  -> address to lookup
+    /// height: u64    -> height of the Block spending the balance
+    /// maturity: u64  -> blocks before a coinbase can be spent
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// i32
+    /// ```
+    pub fn spendable(&self, address: &str, height: u64, maturity: u64) -> i32 {
+        let immature: i32 = self
+            .coinbases
+            .get(address)
+            .map(|coinbases| {
+                coinbases
+                    .iter()
+                    .filter(|(mined, _)| mined + maturity > height)
+                    .map(|(_, amount)| amount)
+                    .sum()
+            })
+            .unwrap_or(0);
+        self.balance(address) - immature
+    }
+
+    /// Compares the balances stored for a set of wallets
+    /// with the balances derived from the chain
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// wallets: &[Wallet] -> wallets to compare
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Vec<BalanceMismatch>
+    /// ```
+    pub fn mismatches(&self, wallets: &[Wallet]) -> Vec<BalanceMismatch> {
+        wallets
+            .iter()
+            .filter(|w| w.balance != self.balance(&w.address))
+            .map(|w| BalanceMismatch {
+                name: w.name.clone(),
+                address: w.address.clone(),
+                stored: w.balance,
+                derived: self.balance(&w.address),
+            })
+            .collect()
+    }
+
+    /// Overwrites the cached balances in wallets.json with
+    /// the balances derived from the chain
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// store: &mut dyn ChainStore -> storage backend holding the wallets
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    pub fn write_wallets(&self, store: &mut dyn ChainStore) -> Result<(), MockchainError> {
+        let mut wallets = store.wallets()?;
+        for wallet in wallets.iter_mut() {
+            wallet.balance = self.balance(&wallet.address);
+        }
+        store.overwrite(DataFile::Wallets, to_records(&wallets, DataFile::Wallets)?)
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_state {
+    use super::*;
+
+    use crate::mods::{
+        config::ChainConfig,
+        genesis::GenesisSpec,
+        helpers::{create_transaction, create_wallet, mine_block, verify_chain},
+        keystore::Keystore,
+        memory::MemoryStore,
+        verification::FailureKind,
+    };
+
+    #[test]
+    fn test_stored_balances_are_checked_against_replay() {
+        let mut store = MemoryStore::new();
+        let mut keystore = Keystore::new(true);
+        store.init(false).unwrap();
+        Block::add_genesis_block(&mut store, &GenesisSpec::default()).unwrap();
+        let alice = create_wallet(&mut store, "alice", &mut keystore).unwrap();
+        let bob = create_wallet(&mut store, "bob", &mut keystore).unwrap();
+        let config = ChainConfig::default();
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+        create_transaction(&mut store, "alice", "bob", 15, 0, &mut keystore).unwrap();
+        mine_block(&mut store, "alice", &config, &mut keystore).unwrap();
+
+        let state = ChainState::from_store(&store).unwrap();
+        assert_eq!(135, state.balance(&alice.address));
+        assert_eq!(15, state.balance(&bob.address));
+        assert!(state.mismatches(&store.wallets().unwrap()).is_empty());
+
+        // a balance edited in wallets.json no longer matches the chain
+        store.write_balance(&bob.address, 1000).unwrap();
+        let mismatches = state.mismatches(&store.wallets().unwrap());
+        assert_eq!(
+            vec![BalanceMismatch {
+                name: String::from("bob"),
+                address: bob.address.clone(),
+                stored: 1000,
+                derived: 15,
+            }],
+            mismatches
+        );
+        assert_eq!(15, Wallet::get_balance(&store, "bob").unwrap());
+        assert_eq!(
+            vec![FailureKind::BalanceMismatch],
+            verify_chain(&store, &config).unwrap().kinds()
+        );
+
+        state.write_wallets(&mut store).unwrap();
+        assert!(verify_chain(&store, &config).unwrap().valid);
+    }
+
+    #[test]
+    fn test_overflowing_cost_is_not_covered() {
+        let mut state = ChainState::default();
+        let transaction = Transaction {
+            hash: String::new(),
+            from_address: String::from("a"),
+            to_address: String::from("b"),
+            public_key: String::new(),
+            amount: i32::MAX,
+            fee: 1,
+            nonce: 0,
+            timestamp: String::new(),
+            signature: String::new(),
+        };
+        assert!(transaction.cost().is_err());
+        assert!(!state.apply(&transaction));
+        assert_eq!(0, state.balance("a"));
+        assert_eq!(0, state.balance("b"));
+    }
+}
@@ -0,0 +1,566 @@
+/*
+    Storage backend abstraction for the chain data
+*/
+
+// 3rd party crates
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{from_value, json, to_value, Value};
+
+// imports
+use super::{
+    base::{Blockchain, KeyPairs, SigningData, Transactions, Wallets},
+    block::Block,
+    crypto::KeyPair,
+    error::MockchainError,
+    log::{Log, LogLevel},
+    signing_data::Signing,
+    transaction::Transaction,
+    wallet::Wallet,
+};
+
+/// Data sets making up the state of a chain. Each
+/// variant maps to one JSON data file in the data
+/// directory of the JSON backend
+///
+/// # Visibility
+/// public
+///
+/// # Variants
+/// ```
+/// Blockchain
+/// Transactions
+/// Wallets
+/// KeyPairs
+/// SigningData
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataFile {
+    Blockchain,
+    Transactions,
+    Wallets,
+    KeyPairs,
+    SigningData,
+}
+
+impl DataFile {
+    // every data set, in the order they are initialised
+    pub const ALL: [DataFile; 5] = [
+        DataFile::Blockchain,
+        DataFile::Transactions,
+        DataFile::Wallets,
+        DataFile::KeyPairs,
+        DataFile::SigningData,
+    ];
+
+    /// Name of the array holding the records of this
+    /// data set e.g. "blockchain" in {"blockchain": []}
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// &'static str
+    /// ```
+    pub fn base(&self) -> &'static str {
+        match self {
+            DataFile::Blockchain => "blockchain",
+            DataFile::Transactions => "transactions",
+            DataFile::Wallets => "wallets",
+            DataFile::KeyPairs => "keypairs",
+            DataFile::SigningData => "signing_data",
+        }
+    }
+
+    /// Name of the JSON file holding this data set
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// &'static str
+    /// ```
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            DataFile::Blockchain => "blockchain.json",
+            DataFile::Transactions => "transactions.json",
+            DataFile::Wallets => "wallets.json",
+            DataFile::KeyPairs => "keypairs.json",
+            DataFile::SigningData => "signing.json",
+        }
+    }
+
+    /// Empty base structure for this data set
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Value, MockchainError>
+    /// ```
+    pub fn empty(&self) -> Result<Value, MockchainError> {
+        match self {
+            DataFile::Blockchain => to_record(Blockchain { blockchain: [] }, *self),
+            DataFile::Transactions => to_record(Transactions { transactions: [] }, *self),
+            DataFile::Wallets => to_record(Wallets { wallets: [] }, *self),
+            DataFile::KeyPairs => to_record(KeyPairs { keypairs: [] }, *self),
+            DataFile::SigningData => to_record(SigningData { signing_data: [] }, *self),
+        }
+    }
+}
+
+/// Converts an object into a serde_json Value ready
+/// to be appended to a data set
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// obj: T         -> an object T that implements Serialize
+/// file: DataFile -> data set the object is written to
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<Value, MockchainError>
+/// ```
+pub fn to_record<T: Serialize>(obj: T, file: DataFile) -> Result<Value, MockchainError> {
+    match to_value(&obj) {
+        Ok(val) => Ok(val),
+        Err(e) => {
+            Log::new_panic(LogLevel::ERROR, 10, Some(vec![file.base().to_string()]));
+            Err(MockchainError::Json(format!(
+                "Failed to parse given object to serde_json Value: {}",
+                e
+            )))
+        }
+    }
+}
+
+/// Gets the array of records held in the base structure
+/// of a data set e.g. the array in {"wallets": [...]}
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// base_data: &mut Value -> parsed data set
+/// file: DataFile        -> data set the Value was read from
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<&mut Vec<Value>, MockchainError>
+/// ```
+pub fn records_mut(
+    base_data: &mut Value,
+    file: DataFile,
+) -> Result<&mut Vec<Value>, MockchainError> {
+    match base_data[file.base()].as_array_mut() {
+        Some(data) => Ok(data),
+        None => {
+            Log::new_panic(LogLevel::ERROR, 2, Some(vec![file.file_name().to_string()]));
+            Err(MockchainError::Corrupt(file.file_name().to_string()))
+        }
+    }
+}
+
+/// Deserializes every record of a data set into a typed
+/// object. The first record that does not match the
+/// expected fields is reported with its position
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// mut base_data: Value -> parsed data set
+/// file: DataFile       -> data set the Value was read from
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<Vec<T>, MockchainError>
+/// ```
+pub fn from_records<T: DeserializeOwned>(
+    mut base_data: Value,
+    file: DataFile,
+) -> Result<Vec<T>, MockchainError> {
+    let records = records_mut(&mut base_data, file)?;
+    let mut typed = Vec::with_capacity(records.len());
+    for (i, record) in records.drain(..).enumerate() {
+        match from_value(record) {
+            Ok(val) => typed.push(val),
+            Err(e) => {
+                let msg = format!("record {} of {}: {}", i, file.file_name(), e);
+                Log::new_panic(LogLevel::ERROR, 17, Some(vec![msg.clone()]));
+                return Err(MockchainError::Schema(msg));
+            }
+        }
+    }
+    Ok(typed)
+}
+
+/// Builds the base structure of a data set from a
+/// slice of typed records e.g. {"wallets": [...]}
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// records: &[T]  -> records to include
+/// file: DataFile -> data set the records belong to
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<Value, MockchainError>
+/// ```
+pub fn to_records<T: Serialize>(records: &[T], file: DataFile) -> Result<Value, MockchainError> {
+    Ok(json!({ file.base(): to_record(records, file)? }))
+}
+
+/// Runs an operation that writes several data sets as
+/// a single unit. Every write is staged until the
+/// operation returns Ok and is thrown away if it
+/// returns an error
+///
+/// # Visibility
+/// public
+///
+/// # Args
+/// ```
+/// store: &mut dyn ChainStore -> storage backend to write to
+/// op: FnOnce                 -> operation making the writes
+/// ```
+///
+/// # Returns
+/// ```
+/// Result<T, MockchainError>
+/// ```
+pub fn atomically<T>(
+    store: &mut dyn ChainStore,
+    op: impl FnOnce(&mut dyn ChainStore) -> Result<T, MockchainError>,
+) -> Result<T, MockchainError> {
+    store.begin()?;
+    match op(store) {
+        Ok(val) => {
+            store.commit()?;
+            Ok(val)
+        }
+        Err(e) => {
+            store.rollback();
+            Err(e)
+        }
+    }
+}
+
+/// Storage backend for blocks, pending transactions,
+/// wallets, key pairs and signing data. Backends only
+/// need to read and replace whole data sets, appending
+/// and balance updates are built on top of those.
+/// Backends that can stage writes override begin,
+/// commit and rollback so multi-file operations
+/// succeed or fail as a unit
+///
+/// # Visibility
+/// public
+pub trait ChainStore {
+    /// Checks whether the chain data has been initialised
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    fn exists(&self) -> bool;
+
+    /// Initialises the data sets when a user first
+    /// runs the app or decides to re-initialise the
+    /// blockchain
+    ///
+    /// # Args
+    /// ```
+    /// preserve_accounts: bool -> option to preserve wallet and key data
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    fn init(&mut self, preserve_accounts: bool) -> Result<(), MockchainError>;
+
+    /// Reads a data set as a serde_json Value Object
+    ///
+    /// # Args
+    /// ```
+    /// file: DataFile -> data set to read
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Value, MockchainError>
+    /// ```
+    fn parse(&self, file: DataFile) -> Result<Value, MockchainError>;
+
+    /// Replaces the full content of a data set
+    ///
+    /// # Args
+    /// ```
+    /// file: DataFile -> data set to replace
+    /// data: Value    -> new base structure e.g. {"wallets": [...]}
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    fn overwrite(&mut self, file: DataFile, data: Value) -> Result<(), MockchainError>;
+
+    /// Starts staging writes until commit or rollback
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    fn begin(&mut self) -> Result<(), MockchainError> {
+        Ok(())
+    }
+
+    /// Applies every write staged since begin
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    fn commit(&mut self) -> Result<(), MockchainError> {
+        Ok(())
+    }
+
+    /// Discards every write staged since begin
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// Nothing
+    fn rollback(&mut self) {}
+
+    /// Completes or discards an operation interrupted
+    /// by a crash. Called before the store is used
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<bool, MockchainError> -> true if an interrupted commit was completed
+    /// ```
+    fn recover(&mut self) -> Result<bool, MockchainError> {
+        Ok(false)
+    }
+
+    /// Gets the PID of the process whose lock forced
+    /// this store to be opened read-only
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Option<String> -> None if the store can be written to
+    /// ```
+    fn locked_by(&self) -> Option<String> {
+        None
+    }
+
+    /// Appends a record to a data set
+    ///
+    /// # Args
+    /// ```
+    /// file: DataFile -> data set to append to
+    /// value: Value   -> record to append
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    fn write(&mut self, file: DataFile, value: Value) -> Result<(), MockchainError> {
+        let mut base_data = self.parse(file)?;
+        records_mut(&mut base_data, file)?.push(value);
+        self.overwrite(file, base_data)
+    }
+
+    /// Write a new value to the balance field of an account
+    ///
+    /// # Args
+    /// ```
+    /// address: &str -> address of account to lookup
+    /// balance: i32  -> new balance to write
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    fn write_balance(&mut self, address: &str, balance: i32) -> Result<(), MockchainError> {
+        let mut wallets = self.wallets()?;
+        if let Some(wallet) = wallets.iter_mut().find(|w| w.address == address) {
+            wallet.balance = balance;
+        }
+        self.overwrite(DataFile::Wallets, to_records(&wallets, DataFile::Wallets)?)
+    }
+
+    /// Reads every Block in the chain
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<Block>, MockchainError>
+    /// ```
+    fn blocks(&self) -> Result<Vec<Block>, MockchainError> {
+        from_records(self.parse(DataFile::Blockchain)?, DataFile::Blockchain)
+    }
+
+    /// Reads every pending Transaction
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<Transaction>, MockchainError>
+    /// ```
+    fn transactions(&self) -> Result<Vec<Transaction>, MockchainError> {
+        from_records(self.parse(DataFile::Transactions)?, DataFile::Transactions)
+    }
+
+    /// Reads every Wallet
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<Wallet>, MockchainError>
+    /// ```
+    fn wallets(&self) -> Result<Vec<Wallet>, MockchainError> {
+        from_records(self.parse(DataFile::Wallets)?, DataFile::Wallets)
+    }
+
+    /// Reads every KeyPair
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<KeyPair>, MockchainError>
+    /// ```
+    fn keypairs(&self) -> Result<Vec<KeyPair>, MockchainError> {
+        from_records(self.parse(DataFile::KeyPairs)?, DataFile::KeyPairs)
+    }
+
+    /// Reads every Signing record
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Vec<Signing>, MockchainError>
+    /// ```
+    fn signing_data(&self) -> Result<Vec<Signing>, MockchainError> {
+        from_records(self.parse(DataFile::SigningData)?, DataFile::SigningData)
+    }
+
+    /// Removes every record from a data set
+    ///
+    /// # Args
+    /// ```
+    /// file: DataFile -> data set to clear
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<(), MockchainError>
+    /// ```
+    fn clear(&mut self, file: DataFile) -> Result<(), MockchainError> {
+        self.overwrite(file, file.empty()?)
+    }
+}
+
+// Testing
+#[cfg(test)]
+mod test_store {
+    use super::*;
+
+    use crate::mods::memory::MemoryStore;
+
+    #[test]
+    fn test_typed_round_trip() {
+        let mut store = MemoryStore::new();
+        store.init(false).unwrap();
+        let wallet = Wallet {
+            name: String::from("alice"),
+            address: "2".repeat(130),
+            balance: 10,
+        };
+        store
+            .write(
+                DataFile::Wallets,
+                to_record(&wallet, DataFile::Wallets).unwrap(),
+            )
+            .unwrap();
+        store.write_balance(&wallet.address, 25).unwrap();
+
+        let wallets = store.wallets().unwrap();
+        assert_eq!(1, wallets.len());
+        assert_eq!("alice", wallets[0].name);
+        assert_eq!(25, wallets[0].balance);
+    }
+
+    #[test]
+    fn test_schema_error_names_record() {
+        let mut store = MemoryStore::new();
+        store.init(false).unwrap();
+        store
+            .overwrite(
+                DataFile::Wallets,
+                json!({ "wallets": [{ "name": "alice", "address": "abc", "balance": "10" }] }),
+            )
+            .unwrap();
+
+        match store.wallets() {
+            Err(MockchainError::Schema(msg)) => {
+                assert!(msg.starts_with("record 0 of wallets.json"))
+            }
+            other => panic!("expected Schema error, got {:?}", other),
+        }
+    }
+}
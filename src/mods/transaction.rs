@@ -1,19 +1,25 @@
 // std library
-use std::fs;
+use std::cmp::Ordering;
 
 // 3rd party crates
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::to_string;
 
 // imports
 use super::{
-    base::Transactions,
+    address::to_address,
     crypto::{hash_transaction, KeyPair},
-    file::FileOps,
-    log::{Log, LogLevel},
+    error::MockchainError,
+    helpers::get_timestamp,
+    keystore::Keystore,
     signing_data::Signing,
+    store::{to_record, ChainStore, DataFile},
 };
-use crate::{SIGNING_DATA_PATH, TRANSACTIONS_PATH};
+
+// sending address of the coinbase transaction paying a miner
+pub const COINBASE: &str = "COINBASE";
+// sending address of the allocations in the genesis block
+pub const GENESIS: &str = "GENESIS";
 
 /// Define a Transaction object
 ///
@@ -25,66 +31,185 @@ use crate::{SIGNING_DATA_PATH, TRANSACTIONS_PATH};
 /// hash: String,
 /// from_address: String,
 /// to_address: String,
+/// public_key: String,     -> public key of the signer, hashing to its address
 /// amount: i32,
+/// fee: i32,               -> paid by the sender to the miner, 0 when omitted
+/// nonce: u64,
+/// timestamp: String,
 /// signature: String
 /// ```
 ///
 /// # Derives
 /// ```
-/// serde::Serialize, Debug
+/// serde::Serialize, serde::Deserialize, Debug, Clone
 /// ```
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Transaction {
     pub hash: String,
     pub from_address: String,
     pub to_address: String,
+    pub public_key: String,
     pub amount: i32,
+    #[serde(default)]
+    pub fee: i32,
+    pub nonce: u64,
+    pub timestamp: String,
     pub signature: String,
 }
 
 impl Transaction {
-    /// Generates a reward Transaction after a Block has
-    /// been mined. Once the current transactions have been
-    /// cleared this transaction is added as the first in
-    /// a new list of transactions
+    /// Adds a fee to an amount, failing rather than
+    /// overflowing when the sum does not fit in an i32
     ///
     /// # Visibility
     /// public
     ///
     /// # Args
     /// ```
-    /// name: String -> name of miner of last Block
+    /// amount: i32 -> amount, or subsidy of a coinbase
+    /// fee: i32    -> fee, or fees of a block
     /// ```
     ///
     /// # Returns
-    /// Nothing
-    pub fn add_reward(name: String) {
-        let to_address = KeyPair::get_key(name.clone(), String::from("public"));
-        let hash = hash_transaction(&String::from("REWARD"), &to_address, &String::from("50"));
+    /// ```
+    /// Result<i32, MockchainError> -> InvalidFee if the sum overflows
+    /// ```
+    pub fn total(amount: i32, fee: i32) -> Result<i32, MockchainError> {
+        amount
+            .checked_add(fee)
+            .ok_or(MockchainError::InvalidFee(fee))
+    }
 
-        let private_key = KeyPair::get_key(name.clone(), String::from("private"));
-        let (signature, signing_key) = KeyPair::sign(&hash, private_key);
+    /// Adds up the fees of a list of transactions
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// transactions: &[Transaction] -> transactions paying the fees
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<i32, MockchainError> -> InvalidFee if the sum overflows
+    /// ```
+    pub fn total_fees(transactions: &[Transaction]) -> Result<i32, MockchainError> {
+        transactions
+            .iter()
+            .try_fold(0, |fees, t| Transaction::total(fees, t.fee))
+    }
 
-        let signing_data = Signing {
-            name: name.clone(),
-            hash: hash.clone(),
-            signing_key,
-            signature: signature.clone(),
-        };
+    /// Gets the total the sender pays, the amount
+    /// plus the fee
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Result<i32, MockchainError> -> InvalidFee if the sum overflows
+    /// ```
+    pub fn cost(&self) -> Result<i32, MockchainError> {
+        Transaction::total(self.amount, self.fee)
+    }
 
-        let reward = Transaction {
-            hash,
-            from_address: String::from("REWARD"),
-            to_address,
-            amount: 50,
-            signature,
-        };
+    /// Gets the size of the transaction in bytes as it is
+    /// serialized into a block
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// usize
+    /// ```
+    pub fn size(&self) -> usize {
+        to_string(self).map(|json| json.len()).unwrap_or(0)
+    }
 
-        FileOps::write(TRANSACTIONS_PATH.as_path(), "transactions", reward);
-        FileOps::write(SIGNING_DATA_PATH.as_path(), "signing_data", signing_data);
+    /// Gets the fee paid per 1000 bytes of block space
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// f64
+    /// ```
+    pub fn fee_rate(&self) -> f64 {
+        match self.size() {
+            0 => 0.0,
+            size => self.fee as f64 * 1000.0 / size as f64,
+        }
     }
 
-    /// Clears all current transactions after mining
+    /// Compares the fee rates of two transactions without
+    /// rounding, by cross multiplying fees and sizes
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// other: &Transaction -> transaction to compare with
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Ordering
+    /// ```
+    pub fn cmp_fee_rate(&self, other: &Transaction) -> Ordering {
+        let lhs = self.fee as i64 * other.size() as i64;
+        let rhs = other.fee as i64 * self.size() as i64;
+        lhs.cmp(&rhs)
+    }
+
+    /// Gets the next nonce for transactions sent from an
+    /// address. Nonces start at 0 and count every
+    /// transaction the address has sent, mined or pending
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// store: &dyn ChainStore -> storage backend holding the chain
+    /// address: &str          -> address of the sender
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<u64, MockchainError>
+    /// ```
+    pub fn next_nonce(store: &dyn ChainStore, address: &str) -> Result<u64, MockchainError> {
+        let mined: Vec<Transaction> = store
+            .blocks()?
+            .into_iter()
+            .flat_map(|b| b.transactions)
+            .collect();
+        let pending = store.transactions()?;
+        Ok(mined
+            .iter()
+            .chain(pending.iter())
+            .filter(|t| t.from_address == address)
+            .map(|t| t.nonce + 1)
+            .max()
+            .unwrap_or(0))
+    }
+
+    /// Checks whether the transaction is the coinbase of
+    /// a Block, paying the miner rather than moving tokens
+    /// between accounts
     ///
     /// # Visibility
     /// public
@@ -93,24 +218,92 @@ impl Transaction {
     /// None
     ///
     /// # Returns
-    /// Nothing
-    pub fn clear() {
-        let t = match to_string(&Transactions { transactions: [] }) {
-            Ok(val) => val,
-            Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 14, None);
-                panic!(
-                    "Failed to parse transactions to String before clearing: {}",
-                    e
-                );
-            }
-        };
-        match fs::write(TRANSACTIONS_PATH.as_path(), t) {
-            Ok(_) => {}
-            Err(e) => {
-                Log::new_panic(LogLevel::ERROR, 9, Some(vec!["transactions".to_string()]));
-                panic!("Failed to write transactions.json: {}", e);
-            }
+    /// ```
+    /// bool
+    /// ```
+    pub fn is_coinbase(&self) -> bool {
+        self.from_address == COINBASE
+    }
+
+    /// Checks whether the transaction is an allocation of
+    /// the genesis block, crediting tokens to an address
+    /// before any block is mined
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// bool
+    /// ```
+    pub fn is_allocation(&self) -> bool {
+        self.from_address == GENESIS
+    }
+
+    /// Generates the coinbase Transaction placed first in
+    /// a Block being mined, paying the miner the subsidy
+    /// for the Block plus the fees of its transactions.
+    /// Coinbases are not sequenced so the nonce is the
+    /// height of the Block instead, keeping the hash of
+    /// every coinbase unique. The signing data is written
+    /// straight away, the Transaction itself is only kept
+    /// in the Block
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// store: &mut dyn ChainStore -> storage backend holding the chain
+    /// name: &str                 -> name of the miner of the Block
+    /// height: u64                -> height of the Block
+    /// amount: i32                -> subsidy plus fees of the Block
+    /// keystore: &mut Keystore    -> keystore unlocking the miners private key
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<Transaction, MockchainError>
+    /// ```
+    pub fn coinbase(
+        store: &mut dyn ChainStore,
+        name: &str,
+        height: u64,
+        amount: i32,
+        keystore: &mut Keystore,
+    ) -> Result<Transaction, MockchainError> {
+        let public_key = KeyPair::get_key(store, name, "public")?;
+        let to_address = to_address(&public_key)?;
+        let timestamp = get_timestamp();
+        let hash = hash_transaction(COINBASE, &to_address, amount, 0, height, &timestamp);
+
+        let private_key = keystore.private_key(store, name)?;
+        let signature = KeyPair::sign(&hash, &private_key)?;
+
+        let signing_data = Signing {
+            name: name.to_string(),
+            hash: hash.clone(),
+            public_key: public_key.clone(),
+            signature: signature.clone(),
         };
+        store.write(
+            DataFile::SigningData,
+            to_record(signing_data, DataFile::SigningData)?,
+        )?;
+
+        Ok(Transaction {
+            hash,
+            from_address: String::from(COINBASE),
+            to_address,
+            public_key,
+            amount,
+            fee: 0,
+            nonce: height,
+            timestamp,
+            signature,
+        })
     }
 }
@@ -0,0 +1,272 @@
+/*
+    Report of every problem found verifying the chain
+*/
+
+// std library
+use std::fmt;
+
+// 3rd party crates
+use serde::{Deserialize, Serialize};
+
+// imports
+use super::block::Block;
+
+/// Kinds of problem verification can find in a chain
+///
+/// # Visibility
+/// public
+///
+/// # Variants
+/// ```
+/// BadGenesis          -> first Block is not the genesis block built from the spec
+/// BadHeader           -> header records the wrong height or an unknown version
+/// BrokenLink          -> previous hash does not match the hash of the Block before
+/// BadBlockHash        -> stored hash does not match the hash of the header
+/// BadProofOfWork      -> header records a difficulty the chain does not expect, or the hash does not meet it
+/// MerkleMismatch      -> merkle root does not match the transactions in the Block
+/// TimestampRegression -> Block is timestamped before the Block it follows
+/// BadCoinbase         -> Block does not start with one coinbase paying the schedule
+/// BadTransactionHash  -> transaction hash does not match its data
+/// BadNonce            -> transaction is replayed or its nonce is out of order
+/// ImmatureCoinbase    -> transaction spends a coinbase before it matures
+/// Overdrawn           -> sender cannot cover the amount and fee
+/// KeyMismatch         -> public key does not hash to the signing address
+/// BadSignature        -> signature cannot be verified against the transaction hash
+/// BalanceMismatch     -> balance in wallets.json differs from the replayed chain
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    BadGenesis,
+    BadHeader,
+    BrokenLink,
+    BadBlockHash,
+    BadProofOfWork,
+    MerkleMismatch,
+    TimestampRegression,
+    BadCoinbase,
+    BadTransactionHash,
+    BadNonce,
+    ImmatureCoinbase,
+    Overdrawn,
+    KeyMismatch,
+    BadSignature,
+    BalanceMismatch,
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            FailureKind::BadGenesis => "bad_genesis",
+            FailureKind::BadHeader => "bad_header",
+            FailureKind::BrokenLink => "broken_link",
+            FailureKind::BadBlockHash => "bad_block_hash",
+            FailureKind::BadProofOfWork => "bad_proof_of_work",
+            FailureKind::MerkleMismatch => "merkle_mismatch",
+            FailureKind::TimestampRegression => "timestamp_regression",
+            FailureKind::BadCoinbase => "bad_coinbase",
+            FailureKind::BadTransactionHash => "bad_transaction_hash",
+            FailureKind::BadNonce => "bad_nonce",
+            FailureKind::ImmatureCoinbase => "immature_coinbase",
+            FailureKind::Overdrawn => "overdrawn",
+            FailureKind::KeyMismatch => "key_mismatch",
+            FailureKind::BadSignature => "bad_signature",
+            FailureKind::BalanceMismatch => "balance_mismatch",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+/// A problem found in the chain. Failures in wallets.json
+/// are not tied to a Block so carry no height or hash
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// kind: FailureKind                -> kind of problem found
+/// height: Option<u64>              -> position in the chain of the Block the problem is in
+/// block_hash: Option<String>       -> hash recorded for the Block
+/// transaction_index: Option<usize> -> position of the transaction in the Block
+/// detail: String                   -> description of the problem
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VerificationFailure {
+    pub kind: FailureKind,
+    pub height: Option<u64>,
+    pub block_hash: Option<String>,
+    pub transaction_index: Option<usize>,
+    pub detail: String,
+}
+
+/// Outcome of verifying the chain, listing every
+/// failure found rather than stopping at the first
+///
+/// # Visibility
+/// public
+///
+/// # Fields
+/// ```
+/// valid: bool                          -> true when no failures were found
+/// blocks: usize                        -> blocks in the verified chain, genesis included
+/// failures: Vec<VerificationFailure>   -> failures in the order they were found
+/// ```
+///
+/// # Derives
+/// ```
+/// serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VerificationReport {
+    pub valid: bool,
+    pub blocks: usize,
+    pub failures: Vec<VerificationFailure>,
+}
+
+impl VerificationReport {
+    /// New report for a chain of blocks with no
+    /// failures found yet
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// blocks: usize -> blocks in the chain, genesis included
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// VerificationReport
+    /// ```
+    pub fn new(blocks: usize) -> VerificationReport {
+        VerificationReport {
+            valid: true,
+            blocks,
+            failures: Vec::new(),
+        }
+    }
+
+    /// Records a failure of a whole Block
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// kind: FailureKind -> kind of problem found
+    /// height: u64       -> position of the Block in the chain
+    /// block: &Block     -> Block the problem is in
+    /// detail: String    -> description of the problem
+    /// ```
+    ///
+    /// # Returns
+    /// Nothing
+    pub fn block_failure(&mut self, kind: FailureKind, height: u64, block: &Block, detail: String) {
+        self.push(kind, Some((height, block)), None, detail);
+    }
+
+    /// Records a failure of a transaction in a Block
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// kind: FailureKind -> kind of problem found
+    /// height: u64       -> position of the Block in the chain
+    /// block: &Block     -> Block holding the transaction
+    /// index: usize      -> position of the transaction in the Block
+    /// detail: String    -> description of the problem
+    /// ```
+    ///
+    /// # Returns
+    /// Nothing
+    pub fn transaction_failure(
+        &mut self,
+        kind: FailureKind,
+        height: u64,
+        block: &Block,
+        index: usize,
+        detail: String,
+    ) {
+        self.push(kind, Some((height, block)), Some(index), detail);
+    }
+
+    /// Records a failure found outside the blocks, such
+    /// as in the balances cached in wallets.json
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// kind: FailureKind -> kind of problem found
+    /// detail: String    -> description of the problem
+    /// ```
+    ///
+    /// # Returns
+    /// Nothing
+    pub fn chain_failure(&mut self, kind: FailureKind, detail: String) {
+        self.push(kind, None, None, detail);
+    }
+
+    /// Gets the kinds of failure found, in order
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// None
+    ///
+    /// # Returns
+    /// ```
+    /// Vec<FailureKind>
+    /// ```
+    pub fn kinds(&self) -> Vec<FailureKind> {
+        self.failures.iter().map(|f| f.kind).collect()
+    }
+
+    /// Appends a failure and marks the report invalid
+    ///
+    /// # Visibility
+    /// private
+    ///
+    /// # Args
+    /// ```
+    /// kind: FailureKind            -> kind of problem found
+    /// block: Option<(u64, &Block)> -> position in the chain and Block the problem is in
+    /// index: Option<usize>         -> position of the transaction in the Block
+    /// detail: String               -> description of the problem
+    /// ```
+    ///
+    /// # Returns
+    /// Nothing
+    fn push(
+        &mut self,
+        kind: FailureKind,
+        block: Option<(u64, &Block)>,
+        index: Option<usize>,
+        detail: String,
+    ) {
+        self.valid = false;
+        self.failures.push(VerificationFailure {
+            kind,
+            height: block.map(|(height, _)| height),
+            block_hash: block.map(|(_, b)| b.hash.clone()),
+            transaction_index: index,
+            detail,
+        });
+    }
+}
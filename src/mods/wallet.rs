@@ -1,12 +1,13 @@
 // 3rd party crates
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 // imports
 use super::{
-    file::FileOps,
-    log::{Log, LogLevel},
+    address::{looks_like_address, validate_address},
+    error::MockchainError,
+    state::ChainState,
+    store::ChainStore,
 };
-use crate::WALLETS_PATH;
 
 /// Defines a Wallet object with name, address, and balance
 ///
@@ -17,14 +18,14 @@ use crate::WALLETS_PATH;
 /// ```
 /// name: String
 /// address: String
-/// balance: u32
+/// balance: i32
 /// ```
 ///
 /// # Derives
 /// ```
-/// serde::Serialize, Debug
+/// serde::Serialize, serde::Deserialize, Debug, Clone
 /// ```
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Wallet {
     pub name: String,
     pub address: String,
@@ -39,28 +40,16 @@ impl Wallet {
     ///
     /// # Args
     /// ```
-    /// name: &String -> name to check for
+    /// store: &dyn ChainStore -> storage backend holding the wallets
+    /// name: &str             -> name to check for
     /// ```
     ///
     /// # Returns
     /// ```
-    /// bool
-    /// ```
-    pub fn name_exists(name: &String) -> bool {
-        let mut base_data = FileOps::parse(WALLETS_PATH.as_path());
-        let wallets = match base_data["wallets"].as_array_mut() {
-            Some(data) => data,
-            None => {
-                Log::new_panic(LogLevel::ERROR, 2, Some(vec!["wallets.json".to_string()]));
-                panic!("Failed to read wallets.json, has the data been modified or the file moved or deleted?");
-            }
-        };
-        for wallet in wallets {
-            if wallet["name"] == *name {
-                return true;
-            }
-        }
-        false
+    /// Result<bool, MockchainError>
+    /// ```
+    pub fn name_exists(store: &dyn ChainStore, name: &str) -> Result<bool, MockchainError> {
+        Ok(store.wallets()?.iter().any(|wallet| wallet.name == name))
     }
 
     /// Reads the public key address of a wallet from
@@ -71,112 +60,104 @@ impl Wallet {
     ///
     /// # Args
     /// ```
-    /// name: String -> name to get address of
+    /// store: &dyn ChainStore -> storage backend holding the wallets
+    /// name: &str             -> name to get address of
     /// ```
     ///
     /// # Returns
     /// ```
-    /// Option<String>
-    /// ```
-    pub fn get_wallet_address(name: &String) -> Option<String> {
-        if !Wallet::name_exists(name) {
-            None
-        } else {
-            let mut base_data = FileOps::parse(WALLETS_PATH.as_path());
-            let wallets = match base_data["wallets"].as_array_mut() {
-                Some(data) => data,
-                None => {
-                    Log::new_panic(LogLevel::ERROR, 2, Some(vec!["wallets.json".to_string()]));
-                    panic!("Failed to read wallets.json, has the data been modified or the file moved or deleted?");
-                }
-            };
-
-            let mut wallet_name = String::from("");
-
-            for wallet in wallets {
-                if wallet["name"] == *name {
-                    wallet_name.push_str(wallet["address"].to_string().as_str());
-                }
-            }
-            Some(wallet_name)
-        }
+    /// Result<Option<String>, MockchainError>
+    /// ```
+    pub fn get_wallet_address(
+        store: &dyn ChainStore,
+        name: &str,
+    ) -> Result<Option<String>, MockchainError> {
+        Ok(store
+            .wallets()?
+            .into_iter()
+            .find(|wallet| wallet.name == name)
+            .map(|wallet| wallet.address))
     }
 
-    /// Updates the value of the wallet balance after
-    /// a transaction has been added to a block
+    /// Resolves the recipient of a transfer to an address.
+    /// The name of a local wallet is tried first, then
+    /// the value itself as an address, which need not
+    /// belong to a local wallet
     ///
     /// # Visibility
     /// public
     ///
     /// # Args
     /// ```
-    /// address: String -> wallet address to update
-    /// amount: i32     -> amount to increment balance by
-    /// op: &str        -> "add" | "subtract"
+    /// store: &dyn ChainStore -> storage backend holding the wallets
+    /// recipient: &str        -> wallet name or address
     /// ```
     ///
     /// # Returns
-    /// Nothing
-    pub fn update_balance(address: String, amount: i32, op: &str) {
-        let mut base_data = FileOps::parse(WALLETS_PATH.as_path());
-        let wallets = match base_data["wallets"].as_array_mut() {
-            Some(data) => data,
-            None => {
-                Log::new_panic(LogLevel::ERROR, 2, Some(vec!["wallets.json".to_string()]));
-                panic!("Failed to read wallets.json, has the data been modified or the file moved or deleted?");
-            }
-        };
-
-        for wallet in wallets {
-            if wallet["address"].to_string() == address {
-                if let Some(val) = wallet["balance"].as_i64() {
-                    let mut balance = val as i32;
-                    if op == "add" {
-                        balance += amount;
-                    }
-                    if op == "subtract" {
-                        balance -= amount;
-                    }
-                    FileOps::write_balance(address, balance);
-                    break;
-                }
-            }
+    /// ```
+    /// Result<String, MockchainError>
+    /// ```
+    pub fn resolve_address(
+        store: &dyn ChainStore,
+        recipient: &str,
+    ) -> Result<String, MockchainError> {
+        if let Some(address) = Wallet::get_wallet_address(store, recipient)? {
+            return Ok(address);
+        }
+        match validate_address(recipient) {
+            Ok(()) => Ok(recipient.to_string()),
+            // an address with a typo is reported as one
+            Err(e) if looks_like_address(recipient) => Err(e),
+            Err(_) => Err(MockchainError::WalletNotFound(recipient.to_string())),
         }
     }
 
-    /// Gets the current balance of this Wallet
+    /// Finds the name of the wallet holding an address
     ///
     /// # Visibility
     /// public
     ///
     /// # Args
     /// ```
-    /// name: &String -> name of account to lookup
+    /// store: &dyn ChainStore -> storage backend holding the wallets
+    /// address: &str          -> address to look up
     /// ```
     ///
     /// # Returns
     /// ```
-    /// i32
-    /// ```
-    pub fn get_balance(name: &String) -> i32 {
-        let mut balance: i32 = 0;
-        let mut base_data = FileOps::parse(WALLETS_PATH.as_path());
-        let wallets = match base_data["wallets"].as_array_mut() {
-            Some(data) => data,
-            None => {
-                Log::new_panic(LogLevel::ERROR, 2, Some(vec!["wallets.json".to_string()]));
-                panic!("Failed to read wallets.json, has the data been modified or the file moved or deleted?");
-            }
-        };
+    /// Result<Option<String>, MockchainError>
+    /// ```
+    pub fn get_wallet_name(
+        store: &dyn ChainStore,
+        address: &str,
+    ) -> Result<Option<String>, MockchainError> {
+        Ok(store
+            .wallets()?
+            .into_iter()
+            .find(|wallet| wallet.address == address)
+            .map(|wallet| wallet.name))
+    }
 
-        for wallet in wallets {
-            if wallet["name"] == *name {
-                if let Some(val) = wallet["balance"].as_i64() {
-                    balance = val as i32;
-                    break;
-                }
-            }
+    /// Gets the current balance of this Wallet by
+    /// replaying the chain
+    ///
+    /// # Visibility
+    /// public
+    ///
+    /// # Args
+    /// ```
+    /// store: &dyn ChainStore -> storage backend holding the wallets
+    /// name: &str             -> name of account to lookup
+    /// ```
+    ///
+    /// # Returns
+    /// ```
+    /// Result<i32, MockchainError>
+    /// ```
+    pub fn get_balance(store: &dyn ChainStore, name: &str) -> Result<i32, MockchainError> {
+        match Wallet::get_wallet_address(store, name)? {
+            Some(address) => Ok(ChainState::from_store(store)?.balance(&address)),
+            None => Ok(0),
         }
-        balance
     }
 }
@@ -0,0 +1,397 @@
+use std::{env, fs, process};
+
+use mockchain_v2::{
+    mods::{
+        config::{ChainConfig, MonetaryPolicy},
+        memory::MemoryStore,
+        profile::{Profiles, DEFAULT_PROFILE},
+        verification::FailureKind,
+    },
+    Mockchain, MockchainError,
+};
+
+#[test]
+fn test_wallet_names_are_unique() {
+    let mut chain = Mockchain::in_memory().unwrap();
+    chain.create_wallet("alice").unwrap();
+
+    match chain.create_wallet("alice") {
+        Err(MockchainError::WalletExists(name)) => assert_eq!("alice", name),
+        other => panic!("expected WalletExists, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_submit_transaction_errors() {
+    let mut chain = Mockchain::in_memory().unwrap();
+    chain.create_wallet("alice").unwrap();
+    chain.create_wallet("bob").unwrap();
+
+    assert!(matches!(
+        chain.submit_transaction("alice", "carol", 10),
+        Err(MockchainError::WalletNotFound(_))
+    ));
+    assert!(matches!(
+        chain.submit_transaction("alice", "bob", 0),
+        Err(MockchainError::InvalidAmount(0))
+    ));
+    assert!(matches!(
+        chain.submit_transaction("alice", "bob", 10),
+        Err(MockchainError::InsufficientFunds(_, 10))
+    ));
+    // the amount plus fee does not fit in an i32
+    assert!(matches!(
+        chain.submit_transaction_with_fee("alice", "bob", i32::MAX, 1),
+        Err(MockchainError::InvalidFee(1))
+    ));
+    assert!(matches!(
+        chain.mine_block("carol"),
+        Err(MockchainError::WalletNotFound(_))
+    ));
+}
+
+#[test]
+fn test_mine_transact_and_verify() {
+    let mut chain = Mockchain::in_memory().unwrap();
+    chain.create_wallet("alice").unwrap();
+    chain.create_wallet("bob").unwrap();
+
+    chain.mine_block("alice").unwrap();
+    chain.mine_block("alice").unwrap();
+    assert_eq!(100, chain.balance("alice").unwrap());
+    // the coinbase of the second block has not matured yet
+    assert_eq!(50, chain.spendable_balance("alice").unwrap());
+
+    let transaction = chain.submit_transaction("alice", "bob", 15).unwrap();
+    assert_eq!(chain.wallet_address("bob").unwrap(), transaction.to_address);
+
+    // pending transfers count against what alice can still spend
+    assert_eq!(35, chain.spendable_balance("alice").unwrap());
+    assert!(matches!(
+        chain.submit_transaction("alice", "bob", 40),
+        Err(MockchainError::InsufficientFunds(_, 40))
+    ));
+
+    chain.mine_block("bob").unwrap();
+    assert_eq!(85, chain.balance("alice").unwrap());
+    assert_eq!(65, chain.balance("bob").unwrap());
+    assert!(chain.verify().unwrap());
+}
+
+#[test]
+fn test_inclusion_proof() {
+    let mut chain = Mockchain::in_memory().unwrap();
+    chain.create_wallet("alice").unwrap();
+    chain.create_wallet("bob").unwrap();
+    chain.mine_block("alice").unwrap();
+    chain.mine_block("alice").unwrap();
+
+    let transaction = chain.submit_transaction("alice", "bob", 10).unwrap();
+    assert!(matches!(
+        chain.inclusion_proof(&transaction.hash),
+        Err(MockchainError::TransactionNotFound(_))
+    ));
+
+    let block = chain.mine_block("bob").unwrap();
+    let proof = chain.inclusion_proof(&transaction.hash).unwrap();
+    assert_eq!(block.hash, proof.block_hash);
+    assert!(chain.verify_inclusion(&proof).unwrap());
+}
+
+#[test]
+fn test_chain_profiles() {
+    let root = env::temp_dir().join(format!("mockchain-profiles-{}", process::id()));
+    let profiles = Profiles::new(&root);
+    fs::create_dir_all(&root).unwrap();
+    fs::write(
+        profiles.config_path(DEFAULT_PROFILE),
+        r#"{"plaintext_keys": true}"#,
+    )
+    .unwrap();
+
+    let mut chain = profiles.open(DEFAULT_PROFILE).unwrap();
+    chain.create_wallet("alice").unwrap();
+    chain.mine_block("alice").unwrap();
+    chain.mine_block("alice").unwrap();
+
+    profiles
+        .clone_profile(DEFAULT_PROFILE, "testnet-a")
+        .unwrap();
+    assert!(matches!(
+        profiles.clone_profile(DEFAULT_PROFILE, "testnet-a"),
+        Err(MockchainError::ProfileExists(_))
+    ));
+    assert_eq!(
+        vec![DEFAULT_PROFILE.to_string(), "testnet-a".to_string()],
+        profiles.list().unwrap()
+    );
+
+    // the clone diverges from the chain it was copied from
+    let mut testnet = profiles.open("testnet-a").unwrap();
+    assert_eq!(100, testnet.balance("alice").unwrap());
+    testnet.mine_block("alice").unwrap();
+    assert_eq!(150, testnet.balance("alice").unwrap());
+    // the first handle still holds the lock on the default chain
+    let reader = profiles.open(DEFAULT_PROFILE).unwrap();
+    assert_eq!(100, reader.balance("alice").unwrap());
+    assert!(reader.locked_by().is_some());
+    assert!(matches!(
+        profiles.delete("testnet-a"),
+        Err(MockchainError::Locked(_))
+    ));
+    drop(testnet);
+
+    profiles.delete("testnet-a").unwrap();
+    assert!(!profiles.exists("testnet-a"));
+    assert!(profiles.delete(DEFAULT_PROFILE).is_err());
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_encrypted_keystore() {
+    let data_path = env::temp_dir().join(format!("mockchain-keystore-{}", process::id()));
+    let mut chain = Mockchain::open(&data_path).unwrap();
+
+    // no prompt means no password to seal the new key with
+    assert!(matches!(
+        chain.create_wallet("alice"),
+        Err(MockchainError::PasswordRequired(_))
+    ));
+    assert!(!chain.wallet_exists("alice").unwrap());
+
+    chain.set_password_prompt(Box::new(|_| Some(String::from("hunter2"))));
+    chain.create_wallet("alice").unwrap();
+    chain.create_wallet("bob").unwrap();
+    let keypairs = fs::read_to_string(data_path.join("keypairs.json")).unwrap();
+    assert!(!keypairs.contains("private_key"));
+    assert!(keypairs.contains("\"kdf\":\"scrypt\""));
+
+    chain.mine_block("alice").unwrap();
+    chain.mine_block("alice").unwrap();
+    chain.submit_transaction("alice", "bob", 10).unwrap();
+
+    // a wrong password leaves the chain as it was
+    chain.set_password_prompt(Box::new(|_| Some(String::from("hunter3"))));
+    let height = chain.blocks().unwrap().len();
+    assert!(matches!(
+        chain.mine_block("alice"),
+        Err(MockchainError::WrongPassword(_))
+    ));
+    assert_eq!(height, chain.blocks().unwrap().len());
+    assert_eq!(1, chain.pending_transactions().unwrap().len());
+    assert!(chain.verify().unwrap());
+
+    drop(chain);
+    fs::remove_dir_all(&data_path).unwrap();
+}
+
+#[test]
+fn test_wallets_restored_from_seed() {
+    let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    let mut chain = Mockchain::in_memory().unwrap();
+    let alice = chain
+        .create_wallet_from_seed("alice", phrase, "", 0)
+        .unwrap();
+    let bob = chain.create_wallet_from_seed("bob", phrase, "", 1).unwrap();
+    assert_ne!(alice.address, bob.address);
+
+    // one key cannot back two wallets
+    match chain.create_wallet_from_seed("carol", phrase, "", 0) {
+        Err(MockchainError::KeyExists(name)) => assert_eq!("alice", name),
+        other => panic!("expected KeyExists, got {:?}", other),
+    }
+    assert!(matches!(
+        chain.create_wallet_from_seed("carol", "abandon about", "", 0),
+        Err(MockchainError::InvalidMnemonic(_))
+    ));
+
+    // wiping the accounts and restoring gives the same addresses back
+    chain.reinitialise(false).unwrap();
+    assert!(!chain.wallet_exists("alice").unwrap());
+    let restored = chain
+        .create_wallet_from_seed("alice", phrase, "", 0)
+        .unwrap();
+    assert_eq!(alice.address, restored.address);
+
+    // restored keys can sign
+    chain.mine_block("alice").unwrap();
+    chain.mine_block("alice").unwrap();
+    assert!(chain.verify().unwrap());
+}
+
+#[test]
+fn test_send_to_address() {
+    let mut chain = Mockchain::in_memory().unwrap();
+    chain.create_wallet("alice").unwrap();
+    chain.mine_block("alice").unwrap();
+    chain.mine_block("alice").unwrap();
+
+    // an account that only exists on another chain
+    let mut other = Mockchain::in_memory().unwrap();
+    let carol = other.create_wallet("carol").unwrap();
+
+    chain
+        .submit_transaction("alice", &carol.address, 30)
+        .unwrap();
+    chain.mine_block("alice").unwrap();
+    assert_eq!(120, chain.balance("alice").unwrap());
+    assert!(!chain.wallet_exists("carol").unwrap());
+    assert!(chain.verify().unwrap());
+
+    // a typo is caught by the checksum, an unknown name is not an address
+    let mut typo: Vec<char> = carol.address.chars().collect();
+    typo[5] = if typo[5] == 'a' { 'b' } else { 'a' };
+    let typo: String = typo.into_iter().collect();
+    assert!(matches!(
+        chain.submit_transaction("alice", &typo, 1),
+        Err(MockchainError::InvalidAddress(_))
+    ));
+    assert!(matches!(
+        chain.submit_transaction("alice", "carol", 1),
+        Err(MockchainError::WalletNotFound(_))
+    ));
+
+    // only local wallets with a key pair can send
+    assert!(matches!(
+        chain.submit_transaction(&carol.address, "alice", 1),
+        Err(MockchainError::WalletNotFound(_))
+    ));
+}
+
+#[test]
+fn test_supply_follows_monetary_policy() {
+    let config = ChainConfig {
+        plaintext_keys: true,
+        monetary_policy: MonetaryPolicy {
+            initial_reward: 40,
+            halving_interval: 2,
+            max_supply: 100,
+        },
+        ..ChainConfig::default()
+    };
+    let mut chain = Mockchain::with_config(Box::new(MemoryStore::new()), config).unwrap();
+    chain.create_wallet("alice").unwrap();
+    for _ in 0..5 {
+        chain.mine_block("alice").unwrap();
+    }
+    assert!(chain.verify().unwrap());
+
+    let supply: Vec<(i32, i64)> = chain
+        .supply_report()
+        .unwrap()
+        .iter()
+        .map(|p| (p.subsidy, p.circulating))
+        .collect();
+    assert_eq!(
+        vec![(0, 0), (40, 40), (40, 80), (20, 100), (0, 100), (0, 100)],
+        supply
+    );
+    assert_eq!(100, chain.balance("alice").unwrap());
+}
+
+#[test]
+fn test_reformatted_chain_verifies() {
+    let data_path = env::temp_dir().join(format!("mockchain-canonical-{}", process::id()));
+    let mut chain = Mockchain::open(&data_path).unwrap();
+    chain.set_password_prompt(Box::new(|_| Some(String::from("hunter2"))));
+    chain.create_wallet("alice").unwrap();
+    chain.create_wallet("bob").unwrap();
+    chain.mine_block("alice").unwrap();
+    chain.mine_block("alice").unwrap();
+    chain.submit_transaction("alice", "bob", 10).unwrap();
+    chain.mine_block("bob").unwrap();
+    drop(chain);
+
+    // pretty print the chain with its keys in sorted rather than field order
+    let path = data_path.join("blockchain.json");
+    let blockchain: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    fs::write(&path, serde_json::to_string_pretty(&blockchain).unwrap()).unwrap();
+
+    let chain = Mockchain::open(&data_path).unwrap();
+    assert!(chain.verify().unwrap());
+
+    drop(chain);
+    fs::remove_dir_all(&data_path).unwrap();
+}
+
+#[test]
+fn test_genesis_spec() {
+    let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+    let alice = Mockchain::in_memory()
+        .unwrap()
+        .create_wallet_from_seed("alice", phrase, "", 0)
+        .unwrap();
+
+    let root = env::temp_dir().join(format!("mockchain-genesis-{}", process::id()));
+    let profiles = Profiles::new(&root);
+    fs::create_dir_all(&root).unwrap();
+    fs::write(
+        profiles.config_path(DEFAULT_PROFILE),
+        r#"{"plaintext_keys": true}"#,
+    )
+    .unwrap();
+    let spec = format!(
+        r#"{{"chain_id": "testnet-g", "difficulty": 1, "allocations": [{{"address": "{}", "amount": 500}}]}}"#,
+        alice.address
+    );
+    fs::write(profiles.genesis_path(DEFAULT_PROFILE), &spec).unwrap();
+
+    // allocations can be spent straight away
+    let mut chain = profiles.open(DEFAULT_PROFILE).unwrap();
+    let genesis = chain.block_at(0).unwrap();
+    assert!(genesis.hash.starts_with('0'));
+    let restored = chain
+        .create_wallet_from_seed("alice", phrase, "", 0)
+        .unwrap();
+    assert_eq!(500, restored.balance);
+    chain.create_wallet("bob").unwrap();
+    chain.submit_transaction("alice", "bob", 100).unwrap();
+    chain.mine_block("bob").unwrap();
+    assert_eq!(400, chain.balance("alice").unwrap());
+    assert_eq!(500, chain.supply_report().unwrap()[0].circulating);
+    assert!(chain.verify().unwrap());
+    drop(chain);
+
+    // the chain no longer matches a different spec
+    fs::write(
+        profiles.genesis_path(DEFAULT_PROFILE),
+        spec.replace("testnet-g", "testnet-h"),
+    )
+    .unwrap();
+    let chain = profiles.open(DEFAULT_PROFILE).unwrap();
+    assert_eq!(
+        vec![FailureKind::BadGenesis],
+        chain.verification_report().unwrap().kinds()
+    );
+    drop(chain);
+
+    // a spec pinning another hash fails verification of an existing chain
+    // and refuses to create a new one
+    let pinned = |hash: &str| format!(r#"{}, "hash": "{}"}}"#, &spec[..spec.len() - 1], hash);
+    fs::write(
+        profiles.genesis_path(DEFAULT_PROFILE),
+        pinned(&"0".repeat(64)),
+    )
+    .unwrap();
+    let chain = profiles.open(DEFAULT_PROFILE).unwrap();
+    assert_eq!(
+        vec![FailureKind::BadGenesis],
+        chain.verification_report().unwrap().kinds()
+    );
+    drop(chain);
+    fs::create_dir_all(profiles.path("pinned")).unwrap();
+    fs::write(profiles.genesis_path("pinned"), pinned(&"0".repeat(64))).unwrap();
+    assert!(matches!(
+        profiles.open("pinned"),
+        Err(MockchainError::Config(_))
+    ));
+    fs::write(
+        profiles.genesis_path(DEFAULT_PROFILE),
+        pinned(&genesis.hash),
+    )
+    .unwrap();
+    assert!(profiles.open(DEFAULT_PROFILE).unwrap().verify().unwrap());
+
+    fs::remove_dir_all(&root).unwrap();
+}